@@ -1,6 +1,6 @@
 use rand::{thread_rng, Rng};
 use serde::{Deserialize, Serialize};
-use std::ops::Not;
+use std::ops::{Not, Range};
 
 use crate::math::{mean, stddev};
 
@@ -16,6 +16,9 @@ pub struct NeuralNetwork {
 
     #[serde(skip)]
     execution_order: Vec<usize>,
+    /// incoming connections of all nodes back to back, see `Node::connections`
+    #[serde(skip)]
+    incoming_connections: Vec<SimpleConnection>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -35,8 +38,9 @@ struct SimpleConnection {
 pub struct Node {
     bias: f32,
     activation_function: ActivationFunction,
+    /// range of the incoming connections in `NeuralNetwork::incoming_connections`
     #[serde(skip)]
-    connections: Vec<SimpleConnection>,
+    connections: Range<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -69,7 +73,7 @@ impl NeuralNetwork {
             nodes.push(Node {
                 bias: 0.0,
                 activation_function: ActivationFunction::Linear,
-                connections: vec![],
+                connections: 0..0,
             })
         }
 
@@ -91,6 +95,7 @@ impl NeuralNetwork {
             nodes,
             connections,
             execution_order: vec![],
+            incoming_connections: vec![],
         };
 
         network.build();
@@ -108,13 +113,20 @@ impl NeuralNetwork {
 
     pub fn run(&self, input: Vec<f32>) -> Vec<f32> {
         let mut node_vales = input;
+
+        Vec::from(self.run_with_buffer(&mut node_vales))
+    }
+
+    /// same as `run` without allocating once the buffer is big enough
+    ///
+    /// the input has to be in the first values of `node_vales`, the returned outputs point into it
+    pub fn run_with_buffer<'a>(&self, node_vales: &'a mut Vec<f32>) -> &'a [f32] {
         node_vales.resize(self.nodes.len(), 0.);
 
         for node_index in &self.execution_order[self.inputs..self.execution_order.len()] {
             let node = &self.nodes[*node_index];
 
-            let mut sum = node
-                .connections
+            let mut sum = self.incoming_connections[node.connections.clone()]
                 .iter()
                 .map(|connection| node_vales[connection.from] * connection.weight)
                 .sum::<f32>();
@@ -130,8 +142,7 @@ impl NeuralNetwork {
             node_vales[*node_index] = value;
         }
 
-        let outputs = &node_vales[self.inputs..(self.inputs + self.outputs)];
-        Vec::from(outputs)
+        &node_vales[self.inputs..(self.inputs + self.outputs)]
     }
 
     pub fn randomize_weights(&mut self, weight_change_chance: f32, mutation_range: f32) {
@@ -167,7 +178,7 @@ impl NeuralNetwork {
         //normalize reward
         let versions: Vec<_> = versions
             .into_iter()
-            .map(|(network, reward)| (network, (reward as f32 - reward_mean) / reward_stddev))
+            .map(|(network, reward)| (network, (reward - reward_mean) / reward_stddev))
             .collect();
 
         // ascent weights
@@ -216,7 +227,7 @@ impl NeuralNetwork {
             self.nodes.push(Node {
                 bias: 0.0,
                 activation_function: ActivationFunction::Relu,
-                connections: vec![],
+                connections: 0..0,
             });
 
             let connection_index = rng.gen_range(0..self.connections.len());
@@ -313,18 +324,22 @@ impl NeuralNetwork {
 
         self.execution_order = execution_order;
 
+        self.incoming_connections.clear();
+
         for (index, node) in self.nodes.iter_mut().enumerate() {
-            let connections = self
-                .connections
-                .iter()
-                .filter(|connection| connection.to == index)
-                .map(|connection| SimpleConnection {
-                    from: connection.from,
-                    weight: connection.weight,
-                })
-                .collect();
+            let start = self.incoming_connections.len();
 
-            node.connections = connections;
+            self.incoming_connections.extend(
+                self.connections
+                    .iter()
+                    .filter(|connection| connection.to == index)
+                    .map(|connection| SimpleConnection {
+                        from: connection.from,
+                        weight: connection.weight,
+                    }),
+            );
+
+            node.connections = start..self.incoming_connections.len();
         }
     }
 }
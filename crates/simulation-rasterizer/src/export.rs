@@ -0,0 +1,119 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use gif::{Encoder, Repeat};
+
+use crate::image::Image;
+
+/// quality against speed of the gif color quantization, 1 is the best and 30 the fastest
+const GIF_SPEED: i32 = 10;
+
+pub fn save_png(image: &Image, path: impl AsRef<Path>) -> io::Result<()> {
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(File::create(path)?),
+        image.width(),
+        image.height(),
+    );
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(image.pixels())?;
+    writer.finish()?;
+
+    Ok(())
+}
+
+/// looping animation, all frames need the size given on creation
+pub struct GifWriter {
+    encoder: Encoder<BufWriter<File>>,
+    width: u16,
+    height: u16,
+    /// hundredths of a second
+    delay: u16,
+}
+
+impl GifWriter {
+    /// `delay` between two frames in hundredths of a second
+    pub fn create(
+        path: impl AsRef<Path>,
+        width: u32,
+        height: u32,
+        delay: u16,
+    ) -> io::Result<GifWriter> {
+        let (width, height) = gif_size(width, height)?;
+
+        let mut encoder = Encoder::new(BufWriter::new(File::create(path)?), width, height, &[])
+            .map_err(io::Error::other)?;
+        encoder
+            .set_repeat(Repeat::Infinite)
+            .map_err(io::Error::other)?;
+
+        Ok(GifWriter {
+            encoder,
+            width,
+            height,
+            delay,
+        })
+    }
+
+    pub fn add_frame(&mut self, image: &Image) -> io::Result<()> {
+        if gif_size(image.width(), image.height())? != (self.width, self.height) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Frame size differs from the gif size",
+            ));
+        }
+
+        let mut pixels = image.pixels().to_vec();
+        let mut frame =
+            gif::Frame::from_rgba_speed(self.width, self.height, &mut pixels, GIF_SPEED);
+        frame.delay = self.delay;
+
+        self.encoder.write_frame(&frame).map_err(io::Error::other)
+    }
+
+    /// writes the end of the gif, dropping the writer does the same but ignores errors
+    pub fn finish(self) -> io::Result<()> {
+        self.encoder.into_inner()?.flush()
+    }
+}
+
+fn gif_size(width: u32, height: u32) -> io::Result<(u16, u16)> {
+    match (u16::try_from(width), u16::try_from(height)) {
+        (Ok(width), Ok(height)) => Ok((width, height)),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Gifs can be at most 65535 pixels wide and high",
+        )),
+    }
+}
+
+/// numbered png files in a directory, for tools like ffmpeg
+pub struct FrameSequence {
+    directory: PathBuf,
+    next: usize,
+}
+
+impl FrameSequence {
+    pub fn create(directory: impl AsRef<Path>) -> io::Result<FrameSequence> {
+        fs::create_dir_all(&directory)?;
+
+        Ok(FrameSequence {
+            directory: directory.as_ref().to_path_buf(),
+            next: 0,
+        })
+    }
+
+    /// saved as frame_00000.png, frame_00001.png, ...
+    pub fn add_frame(&mut self, image: &Image) -> io::Result<()> {
+        save_png(
+            image,
+            self.directory.join(format!("frame_{:05}.png", self.next)),
+        )?;
+        self.next += 1;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,158 @@
+use glam::{vec2, Vec2};
+use simulation::palette::Rgba;
+
+/// shapes are at least this many pixels wide so they do not vanish in small images
+const MIN_RADIUS: f32 = 0.75;
+
+/// rgba between 0 and 1 like in ggez
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color {
+    pub const WHITE: Color = Color::new(1., 1., 1., 1.);
+    pub const BLACK: Color = Color::new(0., 0., 0., 1.);
+    pub const RED: Color = Color::new(1., 0., 0., 1.);
+    pub const GREEN: Color = Color::new(0., 1., 0., 1.);
+    pub const BLUE: Color = Color::new(0., 0., 1., 1.);
+    pub const YELLOW: Color = Color::new(1., 1., 0., 1.);
+
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Color {
+        Color { r, g, b, a }
+    }
+
+    pub fn with_alpha(self, a: f32) -> Color {
+        Color { a, ..self }
+    }
+}
+
+impl From<Rgba> for Color {
+    fn from([r, g, b, a]: Rgba) -> Color {
+        Color::new(r, g, b, a)
+    }
+}
+
+/// rgba8 pixels, rows from top to bottom
+pub struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32, background: Color) -> Image {
+        let pixel = to_rgba8(background);
+
+        Image {
+            width,
+            height,
+            pixels: pixel
+                .iter()
+                .copied()
+                .cycle()
+                .take(width as usize * height as usize * 4)
+                .collect(),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let index = (y as usize * self.width as usize + x as usize) * 4;
+        [
+            self.pixels[index],
+            self.pixels[index + 1],
+            self.pixels[index + 2],
+            self.pixels[index + 3],
+        ]
+    }
+
+    /// alpha blends over the pixel, pixels outside of the image are ignored
+    pub fn blend(&mut self, x: i64, y: i64, color: Color) {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return;
+        }
+
+        let index = (y as usize * self.width as usize + x as usize) * 4;
+        let alpha = color.a.clamp(0., 1.);
+
+        for (channel, value) in [color.r, color.g, color.b].into_iter().enumerate() {
+            let old = self.pixels[index + channel] as f32 / 255.;
+            let new = old * (1. - alpha) + value.clamp(0., 1.) * alpha;
+            self.pixels[index + channel] = (new * 255.).round() as u8;
+        }
+
+        let old_alpha = self.pixels[index + 3] as f32 / 255.;
+        self.pixels[index + 3] = ((old_alpha + alpha * (1. - old_alpha)) * 255.).round() as u8;
+    }
+
+    pub fn fill_rect(&mut self, min: Vec2, max: Vec2, color: Color) {
+        self.fill_where(min, max, color, |_| true);
+    }
+
+    pub fn fill_circle(&mut self, center: Vec2, radius: f32, color: Color) {
+        let radius = radius.max(MIN_RADIUS);
+
+        self.fill_where(center - radius, center + radius, color, |pos| {
+            pos.distance_squared(center) <= radius * radius
+        });
+    }
+
+    /// `radii` along the x and y axis before turning it by `rotation`
+    pub fn fill_ellipse(&mut self, center: Vec2, radii: Vec2, rotation: f32, color: Color) {
+        let radii = radii.max(Vec2::splat(MIN_RADIUS));
+        let unrotate = Vec2::from_angle(-rotation);
+        let reach = radii.max_element();
+
+        self.fill_where(center - reach, center + reach, color, |pos| {
+            let local = unrotate.rotate(pos - center) / radii;
+            local.length_squared() <= 1.
+        });
+    }
+
+    /// with round ends
+    pub fn line(&mut self, from: Vec2, to: Vec2, width: f32, color: Color) {
+        let radius = (width / 2.).max(MIN_RADIUS);
+        let segment = to - from;
+        let length_squared = segment.length_squared().max(f32::EPSILON);
+
+        self.fill_where(from.min(to) - radius, from.max(to) + radius, color, |pos| {
+            let along = ((pos - from).dot(segment) / length_squared).clamp(0., 1.);
+            pos.distance_squared(from + segment * along) <= radius * radius
+        });
+    }
+
+    /// blends every pixel between `min` and `max` whose center is inside the shape
+    fn fill_where(&mut self, min: Vec2, max: Vec2, color: Color, inside: impl Fn(Vec2) -> bool) {
+        let min_x = (min.x.floor() as i64).max(0);
+        let min_y = (min.y.floor() as i64).max(0);
+        let max_x = (max.x.ceil() as i64).min(self.width as i64 - 1);
+        let max_y = (max.y.ceil() as i64).min(self.height as i64 - 1);
+
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                if inside(vec2(x as f32 + 0.5, y as f32 + 0.5)) {
+                    self.blend(x, y, color);
+                }
+            }
+        }
+    }
+}
+
+fn to_rgba8(color: Color) -> [u8; 4] {
+    [color.r, color.g, color.b, color.a].map(|value| (value.clamp(0., 1.) * 255.).round() as u8)
+}
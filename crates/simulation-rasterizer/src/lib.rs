@@ -0,0 +1,49 @@
+pub mod export;
+pub mod image;
+pub mod scene;
+pub mod vector;
+
+pub use scene::{render, RenderOptions};
+pub use vector::render_svg;
+
+#[cfg(test)]
+mod tests {
+    use glam::vec2;
+    use simulation::Simulation;
+
+    use crate::image::{Color, Image};
+    use crate::{render, render_svg, RenderOptions};
+
+    #[test]
+    fn draws_the_scene() {
+        let simulation = Simulation::zero();
+        let image = render(&simulation, &RenderOptions::default(), 200);
+
+        assert_eq!(image.pixels().len(), 200 * 200 * 4);
+        // the nest sits in the middle of the map, the food patch in the bottom right
+        assert_eq!(image.pixel(100, 100), [204, 179, 26, 255]);
+        assert_eq!(image.pixel(170, 170), [0, 255, 0, 255]);
+        assert_eq!(image.pixel(20, 180), [153, 102, 26, 255]);
+    }
+
+    #[test]
+    fn blends_shapes() {
+        let mut image = Image::new(10, 10, Color::BLACK);
+        image.fill_circle(vec2(5., 5.), 2., Color::WHITE.with_alpha(0.5));
+        image.line(vec2(0., 0.5), vec2(10., 0.5), 1., Color::RED);
+
+        assert_eq!(image.pixel(5, 5), [128, 128, 128, 255]);
+        assert_eq!(image.pixel(0, 9), [0, 0, 0, 255]);
+        assert_eq!(image.pixel(9, 0), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn exports_svg() {
+        let simulation = Simulation::zero();
+        let svg = render_svg(&simulation, &RenderOptions::default(), 200).to_string();
+
+        assert!(svg.contains("viewBox=\"-500 -500 1000 1000\""));
+        assert_eq!(svg.matches("<ellipse").count(), simulation.ants().len());
+        assert_eq!(svg.matches("<circle").count(), 50 * 50 + 1);
+    }
+}
@@ -0,0 +1,136 @@
+use std::fs::File;
+use std::io::BufReader;
+
+use clap::Parser;
+use neural_network::NeuralNetwork;
+use simulation::castes::{Caste, SavedColony};
+use simulation::scenario::Scenario;
+use simulation::{Simulation, NEURAL_NETWORK_INPUT_SIZE, NEURAL_NETWORK_OUTPUT_SIZE};
+use simulation_rasterizer::export::{save_png, FrameSequence, GifWriter};
+use simulation_rasterizer::{render, render_svg, RenderOptions};
+
+/// ticks per second of the visualizer, gifs play back at the same speed
+const TICKS_PER_SECOND: usize = 60;
+
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+#[command(propagate_version = true)]
+struct Cli {
+    /// json file of the colony, a random network if not set
+    #[arg(short, long)]
+    path: Option<String>,
+    /// json file with the scenario settings
+    #[arg(short, long)]
+    scenario: Option<String>,
+    /// a single image of the last tick for .png and .svg, an animation for .gif and numbered pngs in this directory otherwise
+    #[arg(short, long)]
+    output: String,
+    #[arg(long, default_value_t = 600)]
+    steps: usize,
+    /// ticks between two frames
+    #[arg(long, default_value_t = 10)]
+    every: usize,
+    /// width and height of the frames in pixels
+    #[arg(long, default_value_t = 800)]
+    size: u32,
+    #[arg(long)]
+    pheromones: bool,
+    #[arg(long)]
+    rays: bool,
+    #[arg(long)]
+    no_wind: bool,
+}
+
+enum Output {
+    Png(String),
+    Svg(String),
+    Gif(GifWriter),
+    Sequence(FrameSequence),
+}
+
+fn main() {
+    let cli = Cli::parse();
+    assert!(cli.every > 0, "Need at least one tick between two frames");
+
+    let castes = cli
+        .path
+        .map(|path| {
+            let file = File::open(path).unwrap();
+            let reader = BufReader::new(file);
+            serde_json::from_reader::<_, SavedColony>(reader)
+                .unwrap()
+                .into_castes()
+        })
+        .unwrap_or_else(|| {
+            let mut network =
+                NeuralNetwork::new(NEURAL_NETWORK_INPUT_SIZE, NEURAL_NETWORK_OUTPUT_SIZE);
+
+            for _ in 0..50 {
+                network.mutate_strucutre();
+            }
+
+            vec![Caste::new(simulation::castes::DEFAULT_CASTE_NAME, network)]
+        });
+
+    let scenario = cli
+        .scenario
+        .map(|path| {
+            let file = File::open(path).unwrap();
+            let reader = BufReader::new(file);
+            serde_json::from_reader::<_, Scenario>(reader).unwrap()
+        })
+        .unwrap_or_default();
+
+    let options = RenderOptions {
+        draw_pheromones: cli.pheromones,
+        draw_rays: cli.rays,
+        draw_wind: !cli.no_wind,
+    };
+
+    let mut output = if cli.output.ends_with(".png") {
+        Output::Png(cli.output)
+    } else if cli.output.ends_with(".svg") {
+        Output::Svg(cli.output)
+    } else if cli.output.ends_with(".gif") {
+        let delay = (cli.every * 100 / TICKS_PER_SECOND).max(1) as u16;
+        Output::Gif(GifWriter::create(&cli.output, cli.size, cli.size, delay).unwrap())
+    } else {
+        Output::Sequence(FrameSequence::create(&cli.output).unwrap())
+    };
+
+    let mut simulation = Simulation::with_scenario(scenario, castes);
+
+    for step in 0..=cli.steps {
+        if step > 0 {
+            simulation.step();
+        }
+
+        if !step.is_multiple_of(cli.every) && step != cli.steps {
+            continue;
+        }
+
+        match &mut output {
+            Output::Png(_) | Output::Svg(_) if step != cli.steps => {}
+            Output::Png(path) => save_png(&render(&simulation, &options, cli.size), path).unwrap(),
+            Output::Svg(path) => {
+                svg::save(path, &render_svg(&simulation, &options, cli.size)).unwrap()
+            }
+            Output::Gif(gif) => gif
+                .add_frame(&render(&simulation, &options, cli.size))
+                .unwrap(),
+            Output::Sequence(sequence) => sequence
+                .add_frame(&render(&simulation, &options, cli.size))
+                .unwrap(),
+        }
+    }
+
+    if let Output::Gif(gif) = output {
+        gif.finish().unwrap();
+    }
+
+    println!(
+        "rendered {} steps, {} food delivered",
+        simulation.stats().step_count,
+        simulation.stats().dropped_of_food
+    );
+}
@@ -0,0 +1,151 @@
+use glam::Vec2;
+use simulation::ants::Ants;
+use simulation::palette;
+use simulation::{Simulation, ANT_HILL_RADIUS, FOOD_SIZE, GAME_SIZE};
+
+use crate::image::{Color, Image};
+
+/// the same layers as in the visualizer, defaults match its start
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    pub draw_pheromones: bool,
+    pub draw_rays: bool,
+    pub draw_wind: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            draw_pheromones: false,
+            draw_rays: false,
+            draw_wind: true,
+        }
+    }
+}
+
+/// draws the whole map into a `size` x `size` image
+pub fn render(simulation: &Simulation, options: &RenderOptions, size: u32) -> Image {
+    let mut image = Image::new(size, size, Color::WHITE);
+    let view = View {
+        scale: size as f32 / (GAME_SIZE * 2.),
+    };
+
+    image.fill_rect(
+        Vec2::ZERO,
+        Vec2::splat(size as f32),
+        palette::map_color(simulation.daylight()).into(),
+    );
+
+    if options.draw_pheromones {
+        draw_pheromones(simulation, &view, &mut image);
+    }
+
+    if options.draw_rays {
+        draw_rays(simulation, &view, &mut image);
+    }
+
+    draw_ants(simulation, &view, &mut image);
+    draw_food(simulation, &view, &mut image);
+
+    if options.draw_wind {
+        draw_wind(simulation, &view, &mut image);
+    }
+
+    for nest in &simulation.scenario().nests {
+        image.fill_circle(
+            view.point(*nest),
+            view.length(ANT_HILL_RADIUS),
+            palette::NEST.into(),
+        );
+    }
+
+    image
+}
+
+/// maps the map from `-GAME_SIZE` to `GAME_SIZE` onto the image
+struct View {
+    scale: f32,
+}
+
+impl View {
+    fn point(&self, pos: Vec2) -> Vec2 {
+        (pos + GAME_SIZE) * self.scale
+    }
+
+    fn length(&self, length: f32) -> f32 {
+        length * self.scale
+    }
+}
+
+fn draw_ants(simulation: &Simulation, view: &View, image: &mut Image) {
+    for ant in simulation.ants().iter() {
+        image.fill_ellipse(
+            view.point(ant.pos()),
+            palette::ANT_RADII * view.scale,
+            ant.dir(),
+            palette::ant_color(ant.carries_food()).into(),
+        );
+    }
+}
+
+fn draw_pheromones(simulation: &Simulation, view: &View, image: &mut Image) {
+    for group in simulation.pheromones().groups.iter().flatten() {
+        let radius = view.length(group.size.min(palette::MAX_PHEROMONE_RADIUS));
+
+        for (pos, intensity) in group.positions.iter().zip(&group.intensities) {
+            let alpha = intensity * group.strength;
+
+            image.fill_circle(
+                view.point(*pos),
+                radius,
+                palette::pheromone_color(group.channel, alpha).into(),
+            );
+        }
+    }
+}
+
+fn draw_food(simulation: &Simulation, view: &View, image: &mut Image) {
+    for food in simulation.foods() {
+        image.fill_circle(
+            view.point(*food.pos()),
+            view.length(FOOD_SIZE),
+            palette::food_color(food.is_cached()).into(),
+        );
+    }
+}
+
+fn draw_rays(simulation: &Simulation, view: &View, image: &mut Image) {
+    for ant in simulation.ants().iter() {
+        let pos = ant.pos();
+        let see_distance =
+            simulation.castes()[ant.caste()].see_distance * simulation.see_distance_share();
+
+        for (direction, ray) in Ants::get_ray_directions(ant.dir()).zip(ant.rays()) {
+            image.line(
+                view.point(pos),
+                view.point(pos + direction * see_distance),
+                view.length(palette::RAY_WIDTH),
+                palette::ray_color(*ray).into(),
+            );
+        }
+    }
+}
+
+fn draw_wind(simulation: &Simulation, view: &View, image: &mut Image) {
+    let Some(wind) = &simulation.scenario().wind else {
+        return;
+    };
+
+    let width = view.length(palette::WIND_WIDTH);
+
+    for arrow in palette::wind_arrows(wind) {
+        for (from, to) in arrow.lines() {
+            image.line(
+                view.point(from),
+                view.point(to),
+                width,
+                palette::WIND.into(),
+            );
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use glam::Vec2;
+use simulation::ants::Ants;
+use simulation::palette::{self, Rgba};
+use simulation::{Simulation, ANT_HILL_RADIUS, FOOD_SIZE, GAME_SIZE};
+use svg::node::element::{Circle, Ellipse, Group, Line, Rectangle};
+use svg::Document;
+
+use crate::scene::RenderOptions;
+
+/// the same scene as `render` in map units, every layer is a group with its name as id
+pub fn render_svg(simulation: &Simulation, options: &RenderOptions, size: u32) -> Document {
+    let mut document = Document::new()
+        .set(
+            "viewBox",
+            (-GAME_SIZE, -GAME_SIZE, GAME_SIZE * 2., GAME_SIZE * 2.),
+        )
+        .set("width", size)
+        .set("height", size)
+        .add(
+            Rectangle::new()
+                .set("x", -GAME_SIZE)
+                .set("y", -GAME_SIZE)
+                .set("width", GAME_SIZE * 2.)
+                .set("height", GAME_SIZE * 2.)
+                .set("fill", rgb(palette::map_color(simulation.daylight()))),
+        );
+
+    if options.draw_pheromones {
+        document = document.add(pheromones(simulation));
+    }
+
+    if options.draw_rays {
+        document = document.add(rays(simulation));
+    }
+
+    document = document.add(ants(simulation)).add(food(simulation));
+
+    if options.draw_wind {
+        document = document.add(wind(simulation));
+    }
+
+    let mut nests = Group::new().set("id", "nests");
+    for nest in &simulation.scenario().nests {
+        nests = nests.add(
+            Circle::new()
+                .set("cx", nest.x)
+                .set("cy", nest.y)
+                .set("r", ANT_HILL_RADIUS)
+                .set("fill", rgb(palette::NEST)),
+        );
+    }
+
+    document.add(nests)
+}
+
+fn ants(simulation: &Simulation) -> Group {
+    let mut group = Group::new().set("id", "ants");
+
+    for ant in simulation.ants().iter() {
+        group = group.add(
+            Ellipse::new()
+                .set("cx", ant.pos().x)
+                .set("cy", ant.pos().y)
+                .set("rx", palette::ANT_RADII.x)
+                .set("ry", palette::ANT_RADII.y)
+                .set(
+                    "transform",
+                    format!(
+                        "rotate({} {} {})",
+                        ant.dir().to_degrees(),
+                        ant.pos().x,
+                        ant.pos().y
+                    ),
+                )
+                .set("fill", rgb(palette::ant_color(ant.carries_food()))),
+        );
+    }
+
+    group
+}
+
+fn pheromones(simulation: &Simulation) -> Group {
+    let mut group = Group::new().set("id", "pheromones");
+
+    for pheromones in simulation.pheromones().groups.iter().flatten() {
+        let radius = pheromones.size.min(palette::MAX_PHEROMONE_RADIUS);
+
+        for (pos, intensity) in pheromones.positions.iter().zip(&pheromones.intensities) {
+            let color =
+                palette::pheromone_color(pheromones.channel, intensity * pheromones.strength);
+
+            group = group.add(
+                Circle::new()
+                    .set("cx", pos.x)
+                    .set("cy", pos.y)
+                    .set("r", radius)
+                    .set("fill", rgb(color))
+                    .set("fill-opacity", color[3].clamp(0., 1.)),
+            );
+        }
+    }
+
+    group
+}
+
+fn food(simulation: &Simulation) -> Group {
+    let mut group = Group::new().set("id", "food");
+
+    for food in simulation.foods() {
+        group = group.add(
+            Circle::new()
+                .set("cx", food.pos().x)
+                .set("cy", food.pos().y)
+                .set("r", FOOD_SIZE)
+                .set("fill", rgb(palette::food_color(food.is_cached()))),
+        );
+    }
+
+    group
+}
+
+fn rays(simulation: &Simulation) -> Group {
+    let mut group = Group::new()
+        .set("id", "rays")
+        .set("stroke-width", palette::RAY_WIDTH);
+
+    for ant in simulation.ants().iter() {
+        let pos = ant.pos();
+        let see_distance =
+            simulation.castes()[ant.caste()].see_distance * simulation.see_distance_share();
+
+        for (direction, ray) in Ants::get_ray_directions(ant.dir()).zip(ant.rays()) {
+            let point = pos + direction * see_distance;
+
+            group = group.add(line(pos, point).set("stroke", rgb(palette::ray_color(*ray))));
+        }
+    }
+
+    group
+}
+
+fn wind(simulation: &Simulation) -> Group {
+    let mut group = Group::new()
+        .set("id", "wind")
+        .set("stroke", rgb(palette::WIND))
+        .set("stroke-opacity", palette::WIND[3])
+        .set("stroke-width", palette::WIND_WIDTH);
+
+    let Some(wind) = &simulation.scenario().wind else {
+        return group;
+    };
+
+    for arrow in palette::wind_arrows(wind) {
+        for (from, to) in arrow.lines() {
+            group = group.add(line(from, to));
+        }
+    }
+
+    group
+}
+
+fn line(from: Vec2, to: Vec2) -> Line {
+    Line::new()
+        .set("x1", from.x)
+        .set("y1", from.y)
+        .set("x2", to.x)
+        .set("y2", to.y)
+        .set("stroke-linecap", "round")
+}
+
+/// without the alpha, svg takes it as a separate opacity
+fn rgb([r, g, b, _]: Rgba) -> String {
+    let [r, g, b] = [r, g, b].map(|value| (value.clamp(0., 1.) * 255.).round());
+    format!("rgb({},{},{})", r, g, b)
+}
@@ -0,0 +1,147 @@
+use glam::{vec2, Vec2};
+use simulation::ants::AntId;
+use simulation::{Simulation, GAME_SIZE};
+
+const MIN_ZOOM: f32 = 0.1;
+const MAX_ZOOM: f32 = 20.;
+/// zoom change per scroll wheel step
+const ZOOM_STEP: f32 = 1.1;
+/// how far from the cursor an ant can be selected, in screen pixels
+const SELECT_DISTANCE: f32 = 20.;
+
+/// which part of the map is visible, screen pixels per map unit is the zoom
+pub struct Camera {
+    pub center: Vec2,
+    pub zoom: f32,
+    pub selected: Option<AntId>,
+    /// keeps the selected ant in the middle of the screen
+    pub following: bool,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Camera {
+            center: Vec2::ZERO,
+            zoom: 1.,
+            selected: None,
+            following: false,
+        }
+    }
+}
+
+impl Camera {
+    /// top left corner and size of the visible map area, for `Canvas::set_screen_coordinates`
+    pub fn visible_area(&self, screen_size: Vec2) -> (Vec2, Vec2) {
+        let size = screen_size / self.zoom;
+
+        (self.center - size / 2., size)
+    }
+
+    pub fn to_world(&self, screen_pos: Vec2, screen_size: Vec2) -> Vec2 {
+        self.center + (screen_pos - screen_size / 2.) / self.zoom
+    }
+
+    /// moves the map along with the mouse, stops following
+    pub fn pan(&mut self, screen_delta: Vec2) {
+        self.center -= screen_delta / self.zoom;
+        self.following = false;
+    }
+
+    /// keeps the point of the map under the cursor in place
+    pub fn zoom_at(&mut self, steps: f32, screen_pos: Vec2, screen_size: Vec2) {
+        let anchor = self.to_world(screen_pos, screen_size);
+
+        self.zoom = (self.zoom * ZOOM_STEP.powf(steps)).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.center = anchor - (screen_pos - screen_size / 2.) / self.zoom;
+    }
+
+    /// the whole map fits on the screen
+    pub fn fit(&mut self, screen_size: Vec2) {
+        self.center = Vec2::ZERO;
+        self.zoom = (screen_size.min_element() / (GAME_SIZE * 2.)).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.following = false;
+    }
+
+    /// selects and follows the closest ant near the cursor, clears the selection if there is none
+    pub fn select(&mut self, simulation: &Simulation, screen_pos: Vec2, screen_size: Vec2) {
+        let pos = self.to_world(screen_pos, screen_size);
+        let max_distance = SELECT_DISTANCE / self.zoom;
+
+        self.selected = simulation
+            .ants()
+            .iter()
+            .map(|ant| (ant.id(), vec2(ant.pos().x, ant.pos().y).distance(pos)))
+            .filter(|(_, distance)| *distance <= max_distance)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(id, _)| id);
+        self.following = self.selected.is_some();
+    }
+
+    pub fn toggle_following(&mut self) {
+        self.following = !self.following && self.selected.is_some();
+    }
+
+    /// call once per frame, drops the selection once the ant is gone
+    pub fn update(&mut self, simulation: &Simulation) {
+        let Some(selected) = self.selected else {
+            return;
+        };
+
+        match simulation.ants().get(selected) {
+            Some(ant) if self.following => self.center = vec2(ant.pos().x, ant.pos().y),
+            Some(_) => {}
+            None => {
+                self.selected = None;
+                self.following = false;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec2;
+    use simulation::Simulation;
+
+    use crate::camera::{Camera, MAX_ZOOM};
+
+    #[test]
+    fn zooms_around_the_cursor() {
+        let screen_size = vec2(800., 600.);
+        let cursor = vec2(650., 120.);
+        let mut camera = Camera {
+            center: vec2(-300., 200.),
+            ..Default::default()
+        };
+
+        // the last steps hit the zoom limit
+        for steps in [3., -7., 0.5, 100.] {
+            let anchor = camera.to_world(cursor, screen_size);
+            camera.zoom_at(steps, cursor, screen_size);
+
+            assert!(camera.to_world(cursor, screen_size).distance(anchor) < 1e-3);
+        }
+
+        assert_eq!(camera.zoom, MAX_ZOOM);
+    }
+
+    #[test]
+    fn forgets_despawned_ants() {
+        let mut simulation = Simulation::zero();
+        let id = simulation.spawn_ant(vec2(100., 50.), 0., 0);
+
+        let screen_size = vec2(800., 600.);
+        let mut camera = Camera::default();
+        camera.select(&simulation, vec2(500., 350.), screen_size);
+        assert_eq!(camera.selected, Some(id));
+        assert!(camera.following);
+
+        camera.update(&simulation);
+        assert_eq!(camera.center, vec2(100., 50.));
+
+        simulation.despawn_ant(id);
+        camera.update(&simulation);
+        assert_eq!(camera.selected, None);
+        assert!(!camera.following);
+    }
+}
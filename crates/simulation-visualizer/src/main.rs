@@ -1,5 +1,5 @@
 use crate::renderer::Renderer;
-use clap::{arg, Parser};
+use clap::Parser;
 use ggez::conf::WindowMode;
 use ggez::event::{self, EventHandler};
 use std::env;
@@ -8,11 +8,19 @@ use std::io::BufReader;
 
 use ggez::graphics::{self, Color, Rect};
 use ggez::input::keyboard::KeyInput;
+use ggez::input::mouse::MouseButton;
 use ggez::winit::event::VirtualKeyCode;
 use ggez::{Context, ContextBuilder, GameError, GameResult};
+use glam::{vec2, Vec2};
 use neural_network::NeuralNetwork;
+use simulation::castes::{Caste, SavedColony};
+use simulation::events::Event;
+use simulation::recorder::{Metric, Recorder};
+use simulation::scenario::Scenario;
 use simulation::timings::avg_duration::AvgDuration;
 use simulation::{Simulation, NEURAL_NETWORK_INPUT_SIZE, NEURAL_NETWORK_OUTPUT_SIZE};
+use simulation_visualizer::camera::Camera;
+use simulation_visualizer::playback::{Playback, BASE_TICKS_PER_SECOND};
 use std::time::Instant;
 
 mod renderer;
@@ -23,19 +31,36 @@ mod renderer;
 struct Cli {
     #[arg(short, long)]
     path: Option<String>,
+    /// json file with the scenario settings
+    #[arg(short, long)]
+    scenario: Option<String>,
+    /// checks the simulation invariants after every tick
+    #[arg(long)]
+    validate: bool,
+    /// records the metrics into this file on X and when closing, csv or json lines
+    #[arg(long)]
+    record: Option<String>,
+    /// comma separated, everything if not set
+    #[arg(long, value_delimiter = ',')]
+    metrics: Vec<Metric>,
+    /// ticks between two samples of the metrics
+    #[arg(long, default_value_t = 60)]
+    record_every: usize,
 }
 
 fn main() {
     let cli = Cli::parse();
 
-    let mut neural_network = cli
+    let castes = cli
         .path
         .map(|path| {
             println!("{}", path);
 
             let file = File::open(path).unwrap();
             let reader = BufReader::new(file);
-            serde_json::from_reader(reader).unwrap()
+            serde_json::from_reader::<_, SavedColony>(reader)
+                .unwrap()
+                .into_castes()
         })
         .or_else(|| {
             let mut network =
@@ -45,11 +70,21 @@ fn main() {
                 network.mutate_strucutre();
             }
 
-            Some(network)
+            Some(vec![Caste::new(
+                simulation::castes::DEFAULT_CASTE_NAME,
+                network,
+            )])
         })
         .unwrap();
 
-    neural_network.build();
+    let scenario = cli
+        .scenario
+        .map(|path| {
+            let file = File::open(path).unwrap();
+            let reader = BufReader::new(file);
+            serde_json::from_reader::<_, Scenario>(reader).unwrap()
+        })
+        .unwrap_or_default();
 
     let (mut ctx, event_loop) = ContextBuilder::new("ai ants", "ToBinio")
         .window_mode(WindowMode::default().resizable(true))
@@ -59,8 +94,18 @@ fn main() {
     let x: Vec<String> = env::args().collect();
     println!("{:?}", x);
 
-    let my_game =
-        SimulationVisualizer::new(&mut ctx, neural_network).expect("could not initialize game");
+    let metrics = if cli.metrics.is_empty() {
+        Metric::ALL.to_vec()
+    } else {
+        cli.metrics
+    };
+    let recording = cli.record.map(|path| Recording {
+        path,
+        recorder: Recorder::new(metrics, cli.record_every),
+    });
+
+    let my_game = SimulationVisualizer::new(&mut ctx, castes, scenario, cli.validate, recording)
+        .expect("could not initialize game");
 
     event::run(ctx, event_loop, my_game);
 }
@@ -70,8 +115,24 @@ struct SimulationVisualizer {
     renderer: Renderer,
     render_state: RenderState,
     timings: Timings,
+    effects: Vec<Effect>,
+    recording: Option<Recording>,
+}
+
+struct Recording {
+    path: String,
+    recorder: Recorder,
 }
 
+/// short lived marker showing where a simulation event happened
+struct Effect {
+    pos: Vec2,
+    color: Color,
+    ticks_left: usize,
+}
+
+const EFFECT_TICKS: usize = 30;
+
 struct Timings {
     render: AvgDuration,
     update: AvgDuration,
@@ -81,37 +142,108 @@ struct RenderState {
     draw_timings: bool,
     draw_pheromones: bool,
     draw_rays: bool,
+    draw_effects: bool,
+    draw_wind: bool,
+    camera: Camera,
+    playback: Playback,
 }
 
 impl SimulationVisualizer {
     pub fn new(
         ctx: &mut Context,
-        neural_network: NeuralNetwork,
+        castes: Vec<Caste>,
+        scenario: Scenario,
+        validate: bool,
+        recording: Option<Recording>,
     ) -> Result<SimulationVisualizer, GameError> {
+        let mut simulation = Simulation::with_scenario(scenario, castes);
+        simulation.profiler_mut().set_enabled(true);
+        simulation.set_events_enabled(true);
+        simulation.set_validation_enabled(validate);
+
+        let mut camera = Camera::default();
+        camera.fit(Vec2::from(ctx.gfx.size()));
+
         Ok(SimulationVisualizer {
-            simulation: Simulation::new(neural_network),
+            simulation,
             renderer: Renderer::new(ctx)?,
             render_state: RenderState {
                 draw_timings: true,
                 draw_pheromones: false,
                 draw_rays: false,
+                draw_effects: true,
+                draw_wind: true,
+                camera,
+                playback: Playback::default(),
             },
             timings: Timings {
                 render: Default::default(),
                 update: Default::default(),
             },
+            effects: vec![],
+            recording,
         })
     }
+
+    fn save_recording(&self) {
+        if let Some(recording) = &self.recording {
+            match recording.recorder.save(&recording.path) {
+                Ok(()) => println!("saved recording to {}", recording.path),
+                Err(error) => println!("could not save recording: {}", error),
+            }
+        }
+    }
+
+    fn tick(&mut self) {
+        self.simulation.step();
+        self.update_effects();
+
+        if let Some(recording) = &mut self.recording {
+            recording.recorder.record(&self.simulation);
+        }
+    }
+
+    fn update_effects(&mut self) {
+        self.effects.retain_mut(|effect| {
+            effect.ticks_left -= 1;
+            effect.ticks_left > 0
+        });
+
+        for event in self.simulation.drain_events() {
+            let (pos, color) = match event {
+                Event::FoodPickedUp { pos, .. } => (pos, Color::GREEN),
+                Event::FoodDelivered { pos, .. } => (pos, Color::YELLOW),
+                Event::AntBounced { pos, .. } => (pos, Color::RED),
+                Event::FoodDropped { pos, .. } => (pos, Color::CYAN),
+                Event::FoodHandedOver { pos, .. } => (pos, Color::MAGENTA),
+                Event::PheromoneSpawned { .. } | Event::PheromoneExpired { .. } => continue,
+            };
+
+            self.effects.push(Effect {
+                pos: Vec2::new(pos.x, pos.y),
+                color,
+                ticks_left: EFFECT_TICKS,
+            });
+        }
+    }
 }
 
 impl EventHandler for SimulationVisualizer {
     fn update(&mut self, ctx: &mut Context) -> GameResult {
         let instant = Instant::now();
 
-        while ctx.time.check_update_time(60) {
-            self.simulation.step();
+        let mut frame_ticks = 0;
+        while ctx.time.check_update_time(BASE_TICKS_PER_SECOND) {
+            frame_ticks += 1;
+        }
+
+        self.render_state.playback.start_frame(frame_ticks);
+        while self.render_state.playback.next_tick() {
+            self.tick();
         }
+        self.render_state.playback.end_frame(ctx.time.delta());
 
+        self.render_state.camera.update(&self.simulation);
         self.timings.update.add(&instant.elapsed());
 
         Ok(())
@@ -121,18 +253,17 @@ impl EventHandler for SimulationVisualizer {
         let instant = Instant::now();
 
         let mut canvas = graphics::Canvas::from_frame(ctx, Color::WHITE);
-        let screen_size = ctx.gfx.size();
-        canvas.set_screen_coordinates(Rect::new(
-            -screen_size.0 / 2.,
-            -screen_size.1 / 2.,
-            screen_size.0,
-            screen_size.1,
-        ));
+        let (corner, size) = self
+            .render_state
+            .camera
+            .visible_area(Vec2::from(ctx.gfx.size()));
+        canvas.set_screen_coordinates(Rect::new(corner.x, corner.y, size.x, size.y));
 
         self.renderer.draw(
             &self.simulation,
             &self.timings,
             &self.render_state,
+            &self.effects,
             &mut canvas,
             ctx,
         )?;
@@ -145,7 +276,7 @@ impl EventHandler for SimulationVisualizer {
 
     fn key_down_event(
         &mut self,
-        _ctx: &mut Context,
+        ctx: &mut Context,
         input: KeyInput,
         _repeated: bool,
     ) -> Result<(), GameError> {
@@ -160,10 +291,85 @@ impl EventHandler for SimulationVisualizer {
                 }
 
                 VirtualKeyCode::R => self.render_state.draw_rays = !self.render_state.draw_rays,
+
+                VirtualKeyCode::E => {
+                    self.render_state.draw_effects = !self.render_state.draw_effects
+                }
+
+                VirtualKeyCode::W => self.render_state.draw_wind = !self.render_state.draw_wind,
+
+                VirtualKeyCode::X => self.save_recording(),
+
+                VirtualKeyCode::F => self.render_state.camera.fit(Vec2::from(ctx.gfx.size())),
+
+                VirtualKeyCode::L => self.render_state.camera.toggle_following(),
+
+                VirtualKeyCode::Space => self.render_state.playback.toggle_pause(),
+
+                VirtualKeyCode::Period => self.render_state.playback.single_tick(),
+
+                VirtualKeyCode::Plus | VirtualKeyCode::Equals | VirtualKeyCode::NumpadAdd => {
+                    self.render_state.playback.faster()
+                }
+
+                VirtualKeyCode::Minus | VirtualKeyCode::NumpadSubtract => {
+                    self.render_state.playback.slower()
+                }
+
+                VirtualKeyCode::U => self.render_state.playback.toggle_unlimited(),
                 _ => {}
             }
         }
 
         Ok(())
     }
+
+    fn mouse_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) -> Result<(), GameError> {
+        if button == MouseButton::Right {
+            self.render_state.camera.select(
+                &self.simulation,
+                vec2(x, y),
+                Vec2::from(ctx.gfx.size()),
+            );
+        }
+
+        Ok(())
+    }
+
+    fn mouse_motion_event(
+        &mut self,
+        ctx: &mut Context,
+        _x: f32,
+        _y: f32,
+        dx: f32,
+        dy: f32,
+    ) -> Result<(), GameError> {
+        if ctx.mouse.button_pressed(MouseButton::Left) {
+            self.render_state.camera.pan(vec2(dx, dy));
+        }
+
+        Ok(())
+    }
+
+    fn mouse_wheel_event(&mut self, ctx: &mut Context, _x: f32, y: f32) -> Result<(), GameError> {
+        self.render_state.camera.zoom_at(
+            y,
+            Vec2::from(ctx.mouse.position()),
+            Vec2::from(ctx.gfx.size()),
+        );
+
+        Ok(())
+    }
+
+    fn quit_event(&mut self, _ctx: &mut Context) -> Result<bool, GameError> {
+        self.save_recording();
+
+        Ok(false)
+    }
 }
@@ -0,0 +1,183 @@
+use std::time::{Duration, Instant};
+
+/// ticks per second at 1x
+pub const BASE_TICKS_PER_SECOND: u32 = 60;
+
+const SPEEDS: [f32; 9] = [0.25, 0.5, 1., 2., 4., 8., 16., 32., 64.];
+const NORMAL_SPEED: usize = 2;
+/// longest a frame keeps stepping, fast speeds skip rendering ticks instead of freezing the window
+const MAX_STEP_TIME: Duration = Duration::from_millis(50);
+
+/// pausing, single ticks and how many ticks run per second
+pub struct Playback {
+    pub paused: bool,
+    /// index into `SPEEDS`
+    speed: usize,
+    /// as many ticks as fit into `MAX_STEP_TIME` every frame
+    pub unlimited: bool,
+    // ticks owed to the speed, carries fractions over to the next frame
+    ticks_due: f32,
+    single_tick: bool,
+    frame_start: Instant,
+    frame_ticks: usize,
+    /// measured, not the target of the speed
+    pub ticks_per_second: f32,
+}
+
+impl Default for Playback {
+    fn default() -> Self {
+        Playback {
+            paused: false,
+            speed: NORMAL_SPEED,
+            unlimited: false,
+            ticks_due: 0.,
+            single_tick: false,
+            frame_start: Instant::now(),
+            frame_ticks: 0,
+            ticks_per_second: 0.,
+        }
+    }
+}
+
+impl Playback {
+    pub fn speed(&self) -> f32 {
+        SPEEDS[self.speed]
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.ticks_due = 0.;
+    }
+
+    /// pauses and runs exactly one tick on the next update
+    pub fn single_tick(&mut self) {
+        self.paused = true;
+        self.single_tick = true;
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed + 1).min(SPEEDS.len() - 1);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = self.speed.saturating_sub(1);
+    }
+
+    pub fn toggle_unlimited(&mut self) {
+        self.unlimited = !self.unlimited;
+    }
+
+    /// `frame_ticks` are the ticks of the 60 per second update timer since the last frame
+    pub fn start_frame(&mut self, frame_ticks: u32) {
+        self.frame_start = Instant::now();
+        self.frame_ticks = 0;
+
+        if !self.paused && !self.unlimited {
+            self.ticks_due += frame_ticks as f32 * self.speed();
+        }
+    }
+
+    /// whether the frame should run another tick, counts it if so
+    pub fn next_tick(&mut self) -> bool {
+        let run = if self.paused {
+            self.single_tick && self.frame_ticks == 0
+        } else if self.frame_start.elapsed() >= MAX_STEP_TIME {
+            false
+        } else if self.unlimited {
+            true
+        } else if self.ticks_due >= 1. {
+            self.ticks_due -= 1.;
+            true
+        } else {
+            false
+        };
+
+        self.frame_ticks += run as usize;
+        run
+    }
+
+    /// `frame_time` since the last frame ended
+    pub fn end_frame(&mut self, frame_time: Duration) {
+        self.single_tick = false;
+        // too slow to keep up, better run behind than pile up ticks forever
+        self.ticks_due = self.ticks_due.min(1.);
+
+        if !frame_time.is_zero() {
+            let rate = self.frame_ticks as f32 / frame_time.as_secs_f32();
+            self.ticks_per_second = self.ticks_per_second * 0.95 + rate * 0.05;
+        }
+    }
+
+    /// for the stats overlay
+    pub fn label(&self) -> String {
+        if self.paused {
+            "paused".to_string()
+        } else if self.unlimited {
+            "unlimited".to_string()
+        } else {
+            format!(
+                "{}x ({} ticks/s)",
+                self.speed(),
+                self.speed() * BASE_TICKS_PER_SECOND as f32
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::playback::Playback;
+
+    /// ticks run by a frame with `frame_ticks` ticks of the update timer
+    fn frame(playback: &mut Playback, frame_ticks: u32) -> usize {
+        playback.start_frame(frame_ticks);
+
+        let mut ticks = 0;
+        while playback.next_tick() {
+            ticks += 1;
+        }
+
+        playback.end_frame(Duration::from_millis(16));
+        ticks
+    }
+
+    #[test]
+    fn single_ticks_while_paused() {
+        let mut playback = Playback::default();
+        playback.toggle_pause();
+
+        assert_eq!(frame(&mut playback, 1), 0);
+
+        playback.single_tick();
+        assert_eq!(frame(&mut playback, 3), 1);
+        assert_eq!(frame(&mut playback, 3), 0);
+        assert!(playback.paused);
+    }
+
+    #[test]
+    fn carries_fractions_over() {
+        let mut playback = Playback::default();
+        playback.slower();
+        playback.slower();
+        assert_eq!(playback.speed(), 0.25);
+
+        let ticks = (0..8).map(|_| frame(&mut playback, 1)).collect::<Vec<_>>();
+        assert_eq!(ticks, [0, 0, 0, 1, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn drops_ticks_it_could_not_keep_up_with() {
+        let mut playback = Playback::default();
+
+        // a slow frame only getting through two of the ten ticks due
+        playback.start_frame(5);
+        assert!(playback.next_tick());
+        assert!(playback.next_tick());
+        playback.end_frame(Duration::from_millis(100));
+
+        assert_eq!(frame(&mut playback, 0), 1);
+        assert_eq!(frame(&mut playback, 0), 0);
+    }
+}
@@ -1,11 +1,12 @@
-use crate::{RenderState, Timings};
+use crate::{Effect, RenderState, Timings, EFFECT_TICKS};
 use ggez::glam::vec2;
-use ggez::graphics::{Canvas, Color, DrawParam, InstanceArray, Mesh, Text, TextFragment};
+use ggez::graphics::{Canvas, Color, DrawParam, InstanceArray, Mesh, Rect, Text, TextFragment};
 use ggez::{graphics, Context, GameError, GameResult};
 use itertools::izip;
-use simulation::ants::{Ants, ANT_SEE_DISTANCE};
+use simulation::ants::Ants;
+use simulation::palette;
 use simulation::{Simulation, ANT_HILL_RADIUS, FOOD_SIZE, GAME_SIZE};
-use std::f32::consts::PI;
+use simulation_visualizer::camera::Camera;
 
 pub struct Renderer {
     ant_mesh: Mesh,
@@ -21,8 +22,8 @@ impl Renderer {
             ctx,
             graphics::DrawMode::fill(),
             vec2(0., 0.),
-            10.0,
-            5.0,
+            palette::ANT_RADII.x,
+            palette::ANT_RADII.y,
             0.1,
             Color::WHITE,
         )?;
@@ -33,7 +34,7 @@ impl Renderer {
             vec2(0., 0.),
             ANT_HILL_RADIUS,
             0.1,
-            Color::new(0.8, 0.7, 0.1, 1.),
+            Color::from(palette::NEST),
         )?;
 
         let pheromone_mesh = Mesh::new_circle(
@@ -51,7 +52,7 @@ impl Renderer {
             vec2(0., 0.),
             FOOD_SIZE,
             0.1,
-            Color::GREEN,
+            Color::WHITE,
         )?;
 
         let map_mesh = Mesh::new_rectangle(
@@ -63,7 +64,7 @@ impl Renderer {
                 w: GAME_SIZE * 2.0,
                 h: GAME_SIZE * 2.0,
             },
-            Color::new(0.6, 0.4, 0.1, 1.),
+            Color::WHITE,
         )?;
 
         Ok(Renderer {
@@ -80,10 +81,15 @@ impl Renderer {
         simulation: &Simulation,
         timings: &Timings,
         render_state: &RenderState,
+        effects: &[Effect],
         canvas: &mut Canvas,
         ctx: &mut Context,
     ) -> GameResult {
-        canvas.draw(&self.map_mesh, DrawParam::from(vec2(0., 0.)));
+        canvas.draw(
+            &self.map_mesh,
+            DrawParam::from(vec2(0., 0.))
+                .color(Color::from(palette::map_color(simulation.daylight()))),
+        );
 
         if render_state.draw_pheromones {
             self.draw_pheromones(simulation, canvas, ctx);
@@ -96,31 +102,70 @@ impl Renderer {
         self.draw_ants(simulation, canvas, ctx);
         self.draw_food(simulation, canvas, ctx);
 
-        canvas.draw(&self.ant_hill_mesh, DrawParam::from(vec2(0., 0.)));
+        if render_state.draw_wind {
+            self.draw_wind(simulation, canvas, ctx);
+        }
+
+        for nest in &simulation.scenario().nests {
+            canvas.draw(&self.ant_hill_mesh, DrawParam::from(vec2(nest.x, nest.y)));
+        }
+
+        if render_state.draw_effects {
+            self.draw_effects(effects, canvas, ctx);
+        }
+
+        self.draw_selection(simulation, &render_state.camera, canvas, ctx)?;
+
+        // the overlay stays in place no matter where the camera looks
+        let screen_size = ctx.gfx.size();
+        canvas.set_screen_coordinates(Rect::new(
+            -screen_size.0 / 2.,
+            -screen_size.1 / 2.,
+            screen_size.0,
+            screen_size.1,
+        ));
 
         if render_state.draw_timings {
-            self.draw_timings(simulation, timings, canvas, ctx);
+            self.draw_timings(simulation, timings, render_state, canvas, ctx);
         }
 
         Ok(())
     }
+
+    /// ring around the selected ant, a few pixels wide at every zoom
+    fn draw_selection(
+        &self,
+        simulation: &Simulation,
+        camera: &Camera,
+        canvas: &mut Canvas,
+        ctx: &mut Context,
+    ) -> GameResult {
+        let Some(ant) = camera.selected.and_then(|id| simulation.ants().get(id)) else {
+            return Ok(());
+        };
+
+        let ring = Mesh::new_circle(
+            ctx,
+            graphics::DrawMode::stroke(3. / camera.zoom),
+            vec2(ant.pos().x, ant.pos().y),
+            20.,
+            0.1,
+            Color::MAGENTA,
+        )?;
+        canvas.draw(&ring, DrawParam::new());
+
+        Ok(())
+    }
+
     fn draw_ants(&self, simulation: &Simulation, canvas: &mut Canvas, ctx: &mut Context) {
         let mut instances = InstanceArray::new(&ctx.gfx, None);
 
-        for ((pos, dir), carries) in simulation
-            .ants()
-            .positions
-            .iter()
-            .zip(simulation.ants().dirs.iter())
-            .zip(simulation.ants().caries_foods.iter())
-        {
-            let color = if *carries { Color::GREEN } else { Color::BLACK };
-
+        for ant in simulation.ants().iter() {
             instances.push(
                 DrawParam::new()
-                    .dest(vec2(pos.x, pos.y))
-                    .rotation(*dir)
-                    .color(color),
+                    .dest(vec2(ant.pos().x, ant.pos().y))
+                    .rotation(ant.dir())
+                    .color(Color::from(palette::ant_color(ant.carries_food()))),
             );
         }
         canvas.draw_instanced_mesh(self.ant_mesh.clone(), &instances, DrawParam::new());
@@ -131,28 +176,17 @@ impl Renderer {
 
         let pheromones = simulation.pheromones();
 
-        for (index, size) in pheromones
-            .sizes
-            .iter()
-            .enumerate()
-            .filter(|(_, size)| size.is_some())
-        {
-            let scale = size.unwrap();
-            //todo extract to function
-            let density = 5. / (scale * scale * PI);
-
-            let index_range = simulation.ants().positions.len() * index
-                ..simulation.ants().positions.len() * (index + 1);
+        for group in pheromones.groups.iter().flatten() {
+            let scale = group.size.min(palette::MAX_PHEROMONE_RADIUS);
 
-            for index in index_range {
-                let color = pheromones.colors[index];
-                let pos = pheromones.positions[index];
+            for (pos, intensity) in group.positions.iter().zip(&group.intensities) {
+                let alpha = intensity * group.strength;
 
                 instances.push(
                     DrawParam::new()
                         .dest(vec2(pos.x, pos.y))
                         .scale(vec2(scale, scale))
-                        .color(Color::new(color.0, color.1, color.2, density)),
+                        .color(Color::from(palette::pheromone_color(group.channel, alpha))),
                 );
             }
         }
@@ -160,13 +194,38 @@ impl Renderer {
         canvas.draw_instanced_mesh(self.pheromone_mesh.clone(), &instances, DrawParam::new());
     }
 
+    fn draw_effects(&self, effects: &[Effect], canvas: &mut Canvas, ctx: &mut Context) {
+        let mut instances = InstanceArray::new(&ctx.gfx, None);
+
+        for effect in effects {
+            let progress = 1. - effect.ticks_left as f32 / EFFECT_TICKS as f32;
+            let scale = 5. + progress * 15.;
+
+            let mut color = effect.color;
+            color.a = 1. - progress;
+
+            instances.push(
+                DrawParam::new()
+                    .dest(effect.pos)
+                    .scale(vec2(scale, scale))
+                    .color(color),
+            );
+        }
+
+        canvas.draw_instanced_mesh(self.pheromone_mesh.clone(), &instances, DrawParam::new());
+    }
+
     fn draw_food(&self, simulation: &Simulation, canvas: &mut Canvas, ctx: &mut Context) {
         let mut instances = InstanceArray::new(&ctx.gfx, None);
 
         for food in simulation.foods() {
             let pos = food.pos();
 
-            instances.push(DrawParam::new().dest(vec2(pos.x, pos.y)));
+            instances.push(
+                DrawParam::new()
+                    .dest(vec2(pos.x, pos.y))
+                    .color(Color::from(palette::food_color(food.is_cached()))),
+            );
         }
 
         canvas.draw_instanced_mesh(self.food_mesh.clone(), &instances, DrawParam::new());
@@ -175,21 +234,41 @@ impl Renderer {
     fn draw_rays(&self, simulation: &Simulation, canvas: &mut Canvas, ctx: &mut Context) {
         let mb = &mut graphics::MeshBuilder::new();
 
-        for (pos, dir, rays) in izip!(
-            &simulation.ants().positions,
-            &simulation.ants().dirs,
-            &simulation.ants().rays
-        ) {
-            for (direction, ray) in izip!(Ants::get_ray_directions(*dir), rays) {
-                let point = *pos + direction * ANT_SEE_DISTANCE;
-                let color = if *ray == -1. {
-                    Color::YELLOW
-                } else {
-                    Color::GREEN
-                };
-
-                mb.line(&[vec2(pos.x, pos.y), vec2(point.x, point.y)], 5., color)
-                    .unwrap();
+        for ant in simulation.ants().iter() {
+            let pos = ant.pos();
+            let see_distance =
+                simulation.castes()[ant.caste()].see_distance * simulation.see_distance_share();
+
+            for (direction, ray) in izip!(Ants::get_ray_directions(ant.dir()), ant.rays()) {
+                let point = pos + direction * see_distance;
+
+                mb.line(
+                    &[vec2(pos.x, pos.y), vec2(point.x, point.y)],
+                    palette::RAY_WIDTH,
+                    Color::from(palette::ray_color(*ray)),
+                )
+                .unwrap();
+            }
+        }
+
+        canvas.draw(&Mesh::from_data(ctx, mb.build()), DrawParam::new());
+    }
+
+    fn draw_wind(&self, simulation: &Simulation, canvas: &mut Canvas, ctx: &mut Context) {
+        let Some(wind) = &simulation.scenario().wind else {
+            return;
+        };
+
+        let mb = &mut graphics::MeshBuilder::new();
+
+        for arrow in palette::wind_arrows(wind) {
+            for (from, to) in arrow.lines() {
+                mb.line(
+                    &[vec2(from.x, from.y), vec2(to.x, to.y)],
+                    palette::WIND_WIDTH,
+                    Color::from(palette::WIND),
+                )
+                .unwrap();
             }
         }
 
@@ -200,38 +279,69 @@ impl Renderer {
         &self,
         simulation: &Simulation,
         timings: &Timings,
+        render_state: &RenderState,
         canvas: &mut Canvas,
         ctx: &mut Context,
     ) {
+        let camera = &render_state.camera;
+        let foraging = simulation.stats().foraging_summary();
+
         let text = format!(
             "Stats:
 fps: {}
 steps: {}
-render time: {:?}
-update time: {:?}
-    ant update time: {:?}
-    ant rays update time: {:?}
-    neural network update time: {:?}
-    keep ants update time: {:?}
-    pheromone update time: {:?}
-    pheromone spawn time: {:?}
-    pheromone remove time: {:?}
-    pick up food time: {:?}
-    drop off food time: {:?}
-            ",
+speed: {}
+tick rate: {:.0} ticks/s
+time of day: {:.2}
+trips: {}
+    mean trip ticks: {:.1}
+    median trip ticks: {:.1}
+    path efficiency: {:.2}
+    deliveries per 1000 ticks: {:.2}
+nest deliveries: {}
+cached food: {}
+handed over food: {}
+crowded share: {:.2}
+zoom: {:.2}{}
+render time: {}
+update time: {}
+{}            ",
             ctx.time.fps(),
             simulation.stats().step_count,
-            timings.render,
-            timings.update,
-            simulation.timings().ant_updates,
-            simulation.timings().see_food,
-            simulation.timings().neural_network_updates,
-            simulation.timings().keep_ants,
-            simulation.timings().pheromone_updates,
-            simulation.timings().pheromone_spawn,
-            simulation.timings().pheromone_remove,
-            simulation.timings().pick_up_food,
-            simulation.timings().drop_of_food
+            render_state.playback.label(),
+            render_state.playback.ticks_per_second,
+            simulation.time_of_day(),
+            foraging.trips,
+            foraging.mean_trip_ticks.unwrap_or(0.),
+            foraging.median_trip_ticks.unwrap_or(0.),
+            foraging.path_efficiency.unwrap_or(0.),
+            foraging.deliveries_per_1000_ticks,
+            simulation
+                .stats()
+                .nests()
+                .iter()
+                .map(|nest| nest.delivered_food.to_string())
+                .collect::<Vec<_>>()
+                .join(" / "),
+            simulation.stats().cached_food,
+            simulation.stats().handed_over_food,
+            foraging.crowded_share,
+            camera.zoom,
+            if camera.following { " following" } else { "" },
+            timings.render.summary(),
+            timings.update.summary(),
+            simulation
+                .profiler()
+                .spans()
+                .iter()
+                .map(|span| format!(
+                    "{}{} time: {}",
+                    "    ".repeat(span.depth + 1),
+                    span.name,
+                    span.timing.summary()
+                ))
+                .collect::<Vec<_>>()
+                .join("\n")
         );
 
         let text = Text::new(TextFragment::new(text));
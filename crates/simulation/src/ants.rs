@@ -2,28 +2,342 @@ use std::f32::consts::PI;
 
 use glam::Vec2;
 
+use crate::pheromones::PHEROMONE_CHANNEL_COUNT;
+
 pub const ANT_SPEED: f32 = 100.;
 pub const ANT_PICK_UP_DISTANCE: f32 = 10.;
 
 pub const ANT_RAY_COUNT: usize = 7;
 //see 90° evenly
 pub const ANT_RAY_ANGLE: f32 = ((PI * 2.) / 4.) / ANT_RAY_COUNT as f32;
+/// angle of each ray relative to the ant direction
+pub const ANT_RAY_OFFSETS: [f32; ANT_RAY_COUNT] = ray_offsets();
 pub const ANT_SEE_DISTANCE: f32 = 50.;
 
+/// antennas sensing pheromones point this far to the left and right
+pub const ANT_ANTENNA_ANGLE: f32 = PI / 4.;
+pub const ANT_ANTENNA_LENGTH: f32 = 10.;
+
+/// ants closer than twice this overlap when collision is enabled
+pub const ANT_COLLISION_RADIUS: f32 = 3.;
+/// speed lost per neighbour inside the nest, traffic jam at the entrance
+pub const ANT_CROWDING_SLOWDOWN: f32 = 0.1;
+
+const fn ray_offsets() -> [f32; ANT_RAY_COUNT] {
+    const BASE_ANGLE: f32 = (ANT_RAY_COUNT / 2) as f32 * -ANT_RAY_ANGLE;
+
+    let mut offsets = [0.; ANT_RAY_COUNT];
+    let mut i = 0;
+
+    while i < ANT_RAY_COUNT {
+        offsets[i] = BASE_ANGLE + ANT_RAY_ANGLE * i as f32;
+        i += 1;
+    }
+
+    offsets
+}
+
+/// stays the same for the whole life of an ant, unlike its index into the columns of `Ants`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AntId(usize);
+
+impl AntId {
+    pub fn raw(&self) -> usize {
+        self.0
+    }
+}
+
+/// all ants stored as columns, every column has one entry per living ant
+#[derive(Default)]
 pub struct Ants {
-    //todo dont all pub
-    pub positions: Vec<Vec2>,
-    pub dirs: Vec<f32>,
-    pub target_dirs: Vec<f32>,
-    pub caries_foods: Vec<bool>,
-    pub pheromone_colors: Vec<(f32, f32, f32)>,
-    pub rays: Vec<Vec<f32>>,
+    pub(crate) ids: Vec<AntId>,
+    pub(crate) positions: Vec<Vec2>,
+    pub(crate) dirs: Vec<f32>,
+    pub(crate) target_dirs: Vec<f32>,
+    /// index into `Simulation::castes`
+    pub(crate) castes: Vec<usize>,
+    pub(crate) carried_foods: Vec<usize>,
+    /// the ant wants to get rid of its food wherever it is
+    pub(crate) drops_food: Vec<bool>,
+    /// overlapping ants, only counted with collision enabled
+    pub(crate) neighbours: Vec<usize>,
+    /// deposit intensity per pheromone channel, 0 = nothing is deposited
+    pub(crate) pheromone_intensities: Vec<[f32; PHEROMONE_CHANNEL_COUNT]>,
+    /// sensed strength per pheromone channel, left antenna followed by right antenna
+    pub(crate) pheromone_senses: Vec<[f32; PHEROMONE_CHANNEL_COUNT * 2]>,
+    /// food scent at the left and right antenna
+    pub(crate) scent_senses: Vec<[f32; 2]>,
+    pub(crate) rays: Vec<[f32; ANT_RAY_COUNT]>,
+
+    // column index per AntId, None once the ant got despawned
+    indices: Vec<Option<usize>>,
+}
+
+/// read only view of a single ant
+#[derive(Clone, Copy)]
+pub struct Ant<'a> {
+    ants: &'a Ants,
+    index: usize,
 }
 
 impl Ants {
+    /// left and right antenna tip
+    pub fn get_antenna_positions(pos: Vec2, dir: f32) -> [Vec2; 2] {
+        [-ANT_ANTENNA_ANGLE, ANT_ANTENNA_ANGLE]
+            .map(|angle| pos + Vec2::from_angle(dir + angle) * ANT_ANTENNA_LENGTH)
+    }
+
     pub fn get_ray_directions(dir: f32) -> impl Iterator<Item = Vec2> {
-        const BASE_ANGLE: f32 = (ANT_RAY_COUNT / 2) as f32 * -ANT_RAY_ANGLE;
-        (0..ANT_RAY_COUNT)
-            .map(move |i| Vec2::from_angle(BASE_ANGLE + ANT_RAY_ANGLE * i as f32 + dir))
+        ANT_RAY_OFFSETS
+            .into_iter()
+            .map(move |offset| Vec2::from_angle(offset + dir))
+    }
+
+    pub fn ray_directions(dir: f32) -> [Vec2; ANT_RAY_COUNT] {
+        ANT_RAY_OFFSETS.map(|offset| Vec2::from_angle(offset + dir))
+    }
+
+    pub fn spawn(&mut self, pos: Vec2, dir: f32, caste: usize) -> AntId {
+        let id = AntId(self.indices.len());
+        self.indices.push(Some(self.ids.len()));
+
+        self.ids.push(id);
+        self.positions.push(pos);
+        self.dirs.push(dir);
+        self.target_dirs.push(dir);
+        self.castes.push(caste);
+        self.carried_foods.push(0);
+        self.drops_food.push(false);
+        self.neighbours.push(0);
+        self.pheromone_intensities
+            .push([0.; PHEROMONE_CHANNEL_COUNT]);
+        self.pheromone_senses
+            .push([0.; PHEROMONE_CHANNEL_COUNT * 2]);
+        self.scent_senses.push([0.; 2]);
+        self.rays.push([0.; ANT_RAY_COUNT]);
+
+        id
+    }
+
+    /// returns false if the ant does not exist (anymore)
+    pub fn despawn(&mut self, id: AntId) -> bool {
+        let Some(index) = self.index_of(id) else {
+            return false;
+        };
+
+        self.ids.swap_remove(index);
+        self.positions.swap_remove(index);
+        self.dirs.swap_remove(index);
+        self.target_dirs.swap_remove(index);
+        self.castes.swap_remove(index);
+        self.carried_foods.swap_remove(index);
+        self.drops_food.swap_remove(index);
+        self.neighbours.swap_remove(index);
+        self.pheromone_intensities.swap_remove(index);
+        self.pheromone_senses.swap_remove(index);
+        self.scent_senses.swap_remove(index);
+        self.rays.swap_remove(index);
+
+        self.indices[id.0] = None;
+
+        // the last ant got moved into the freed slot
+        if let Some(moved) = self.ids.get(index) {
+            self.indices[moved.0] = Some(index);
+        }
+
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// length of every column, they all have to match `len`
+    pub(crate) fn column_lengths(&self) -> [(&'static str, usize); 12] {
+        [
+            ("ids", self.ids.len()),
+            ("positions", self.positions.len()),
+            ("dirs", self.dirs.len()),
+            ("target_dirs", self.target_dirs.len()),
+            ("castes", self.castes.len()),
+            ("carried_foods", self.carried_foods.len()),
+            ("drops_food", self.drops_food.len()),
+            ("neighbours", self.neighbours.len()),
+            ("pheromone_intensities", self.pheromone_intensities.len()),
+            ("pheromone_senses", self.pheromone_senses.len()),
+            ("scent_senses", self.scent_senses.len()),
+            ("rays", self.rays.len()),
+        ]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn contains(&self, id: AntId) -> bool {
+        self.index_of(id).is_some()
+    }
+
+    /// current column index of the ant, only valid until the next despawn
+    pub fn index_of(&self, id: AntId) -> Option<usize> {
+        self.indices.get(id.0).copied().flatten()
+    }
+
+    pub fn get(&self, id: AntId) -> Option<Ant<'_>> {
+        self.index_of(id).map(|index| Ant { ants: self, index })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Ant<'_>> {
+        (0..self.len()).map(|index| Ant { ants: self, index })
+    }
+
+    pub fn ids(&self) -> &[AntId] {
+        &self.ids
+    }
+
+    pub fn positions(&self) -> &[Vec2] {
+        &self.positions
+    }
+
+    pub fn dirs(&self) -> &[f32] {
+        &self.dirs
+    }
+
+    pub fn target_dirs(&self) -> &[f32] {
+        &self.target_dirs
+    }
+
+    pub fn castes(&self) -> &[usize] {
+        &self.castes
+    }
+
+    pub fn carried_foods(&self) -> &[usize] {
+        &self.carried_foods
+    }
+
+    pub fn drops_food(&self) -> &[bool] {
+        &self.drops_food
+    }
+
+    pub fn neighbours(&self) -> &[usize] {
+        &self.neighbours
+    }
+
+    pub fn pheromone_intensities(&self) -> &[[f32; PHEROMONE_CHANNEL_COUNT]] {
+        &self.pheromone_intensities
+    }
+
+    pub fn pheromone_senses(&self) -> &[[f32; PHEROMONE_CHANNEL_COUNT * 2]] {
+        &self.pheromone_senses
+    }
+
+    pub fn scent_senses(&self) -> &[[f32; 2]] {
+        &self.scent_senses
+    }
+
+    pub fn rays(&self) -> &[[f32; ANT_RAY_COUNT]] {
+        &self.rays
+    }
+}
+
+impl<'a> Ant<'a> {
+    pub fn id(&self) -> AntId {
+        self.ants.ids[self.index]
+    }
+
+    pub fn pos(&self) -> Vec2 {
+        self.ants.positions[self.index]
+    }
+
+    pub fn dir(&self) -> f32 {
+        self.ants.dirs[self.index]
+    }
+
+    pub fn target_dir(&self) -> f32 {
+        self.ants.target_dirs[self.index]
+    }
+
+    pub fn caste(&self) -> usize {
+        self.ants.castes[self.index]
+    }
+
+    pub fn carried_food(&self) -> usize {
+        self.ants.carried_foods[self.index]
+    }
+
+    pub fn carries_food(&self) -> bool {
+        self.carried_food() > 0
+    }
+
+    pub fn drops_food(&self) -> bool {
+        self.ants.drops_food[self.index]
+    }
+
+    pub fn neighbours(&self) -> usize {
+        self.ants.neighbours[self.index]
+    }
+
+    pub fn pheromone_intensities(&self) -> [f32; PHEROMONE_CHANNEL_COUNT] {
+        self.ants.pheromone_intensities[self.index]
+    }
+
+    pub fn pheromone_senses(&self) -> [f32; PHEROMONE_CHANNEL_COUNT * 2] {
+        self.ants.pheromone_senses[self.index]
+    }
+
+    pub fn scent_senses(&self) -> [f32; 2] {
+        self.ants.scent_senses[self.index]
+    }
+
+    pub fn rays(&self) -> &'a [f32] {
+        &self.ants.rays[self.index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use crate::ants::{Ants, ANT_COLLISION_RADIUS};
+    use crate::grid::Grid;
+    use crate::timings::profiler::Profiler;
+    use crate::{Simulation, GAME_SIZE};
+
+    #[test]
+    fn despawn_keeps_ids_stable() {
+        let mut ants = Ants::default();
+
+        let first = ants.spawn(Vec2::new(1., 0.), 0., 0);
+        let second = ants.spawn(Vec2::new(2., 0.), 0., 0);
+        let third = ants.spawn(Vec2::new(3., 0.), 0., 0);
+
+        assert!(ants.despawn(first));
+        assert!(!ants.despawn(first));
+
+        assert_eq!(ants.len(), 2);
+        assert!(ants.get(first).is_none());
+        assert_eq!(ants.get(second).unwrap().pos(), Vec2::new(2., 0.));
+        assert_eq!(ants.get(third).unwrap().pos(), Vec2::new(3., 0.));
+        assert_eq!(ants.get(third).unwrap().id(), third);
+    }
+
+    #[test]
+    fn collision_separates_ants() {
+        let mut ants = Ants::default();
+        let mut grid = Grid::new(100, GAME_SIZE);
+        let mut profiler = Profiler::new();
+
+        let first = ants.spawn(Vec2::new(100., 100.), 0., 0);
+        let second = ants.spawn(Vec2::new(100., 100.), 0., 0);
+
+        Simulation::collide_ants(&mut ants, &mut grid, &mut vec![], &mut profiler);
+        assert_eq!(ants.get(first).unwrap().neighbours(), 1);
+
+        let distance = ants
+            .get(first)
+            .unwrap()
+            .pos()
+            .distance(ants.get(second).unwrap().pos());
+        assert!(distance >= ANT_COLLISION_RADIUS * 2. - 0.01);
     }
 }
@@ -0,0 +1,165 @@
+use neural_network::NeuralNetwork;
+use serde::{Deserialize, Serialize};
+
+use crate::ants::{ANT_SEE_DISTANCE, ANT_SPEED};
+use crate::{NEURAL_NETWORK_INPUT_SIZE, NEURAL_NETWORK_OUTPUT_SIZE};
+
+pub const DEFAULT_CASTE_NAME: &str = "worker";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Caste {
+    pub name: String,
+    /// share of the colony, relative to the ratios of all other castes
+    pub ratio: f32,
+    pub speed: f32,
+    pub see_distance: f32,
+    pub carry_capacity: usize,
+
+    #[serde(default = "random_network")]
+    pub neural_network: NeuralNetwork,
+}
+
+impl Default for Caste {
+    fn default() -> Self {
+        Caste::new(DEFAULT_CASTE_NAME, random_network())
+    }
+}
+
+impl Caste {
+    pub fn new(name: impl Into<String>, neural_network: NeuralNetwork) -> Caste {
+        Caste {
+            name: name.into(),
+            ratio: 1.,
+            speed: ANT_SPEED,
+            see_distance: ANT_SEE_DISTANCE,
+            carry_capacity: 1,
+            neural_network,
+        }
+    }
+
+    pub fn with_neural_network(&self, neural_network: NeuralNetwork) -> Caste {
+        Caste {
+            neural_network,
+            ..self.clone()
+        }
+    }
+
+    /// true if everything but the neural-network matches `Caste::default`
+    pub fn has_default_traits(&self) -> bool {
+        self.name == DEFAULT_CASTE_NAME
+            && self.ratio == 1.
+            && self.speed == ANT_SPEED
+            && self.see_distance == ANT_SEE_DISTANCE
+            && self.carry_capacity == 1
+    }
+}
+
+fn random_network() -> NeuralNetwork {
+    NeuralNetwork::new(NEURAL_NETWORK_INPUT_SIZE, NEURAL_NETWORK_OUTPUT_SIZE)
+}
+
+/// caste index for each of `count` ants
+///
+/// castes are interleaved so that every part of the spawn circle gets a mix of all castes
+pub fn assign_castes(castes: &[Caste], count: usize) -> Vec<usize> {
+    let ratio_sum: f32 = castes.iter().map(|caste| caste.ratio).sum();
+    let mut assigned = vec![0; castes.len()];
+
+    (0..count)
+        .map(|index| {
+            let (caste, _) = castes
+                .iter()
+                .enumerate()
+                .map(|(caste, data)| {
+                    let target = data.ratio / ratio_sum * (index + 1) as f32;
+                    (caste, target - assigned[caste] as f32)
+                })
+                // on ties prefer the caste listed first
+                .max_by(|(caste_a, a), (caste_b, b)| a.total_cmp(b).then(caste_b.cmp(caste_a)))
+                .expect("a colony needs at least one caste");
+
+            assigned[caste] += 1;
+            caste
+        })
+        .collect()
+}
+
+/// file format of saved colonies
+///
+/// a plain network is the format used before castes existed and is loaded as a single default caste
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SavedColony {
+    Castes(Vec<Caste>),
+    Network(NeuralNetwork),
+}
+
+impl SavedColony {
+    pub fn from_castes(mut castes: Vec<Caste>) -> SavedColony {
+        if castes.len() == 1 && castes[0].has_default_traits() {
+            SavedColony::Network(castes.remove(0).neural_network)
+        } else {
+            SavedColony::Castes(castes)
+        }
+    }
+
+    pub fn into_castes(self) -> Vec<Caste> {
+        match self {
+            SavedColony::Castes(castes) => castes,
+            SavedColony::Network(neural_network) => {
+                vec![Caste::new(DEFAULT_CASTE_NAME, neural_network)]
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use neural_network::NeuralNetwork;
+
+    use crate::castes::{assign_castes, Caste};
+    use crate::{Simulation, NEURAL_NETWORK_INPUT_SIZE, NEURAL_NETWORK_OUTPUT_SIZE};
+
+    fn caste(ratio: f32) -> Caste {
+        let mut caste = Caste::new(
+            "test",
+            NeuralNetwork::zero(NEURAL_NETWORK_INPUT_SIZE, NEURAL_NETWORK_OUTPUT_SIZE),
+        );
+        caste.ratio = ratio;
+        caste
+    }
+
+    #[test]
+    fn assigns_in_ratio() {
+        let castes = vec![caste(1.), caste(3.)];
+
+        let assigned = assign_castes(&castes, 200);
+
+        assert_eq!(assigned.iter().filter(|caste| **caste == 0).count(), 50);
+        assert_eq!(assigned.iter().filter(|caste| **caste == 1).count(), 150);
+    }
+
+    #[test]
+    fn interleaves_castes() {
+        let castes = vec![caste(1.), caste(1.)];
+
+        let assigned = assign_castes(&castes, 4);
+
+        assert_eq!(assigned, vec![0, 1, 0, 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "needs to carry at least one food")]
+    fn rejects_castes_carrying_nothing() {
+        let mut caste = caste(1.);
+        caste.carry_capacity = 0;
+
+        Simulation::with_castes(vec![caste]);
+    }
+
+    #[test]
+    #[should_panic(expected = "needs a positive ratio")]
+    fn rejects_castes_without_ratio() {
+        Simulation::with_castes(vec![caste(1.), caste(0.)]);
+    }
+}
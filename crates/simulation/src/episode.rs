@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+
+/// why an episode ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Termination {
+    /// no food left on the map and none carried
+    AllFoodDelivered,
+    /// no food got picked up or delivered for `EpisodePolicy::no_progress_ticks`
+    NoProgress,
+    ColonyExtinct,
+    MaxSteps,
+}
+
+/// when to stop running a simulation
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct EpisodePolicy {
+    pub max_steps: usize,
+    pub stop_when_food_delivered: bool,
+    /// never stops for missing progress if not set
+    pub no_progress_ticks: Option<usize>,
+    pub stop_when_extinct: bool,
+}
+
+impl Default for EpisodePolicy {
+    fn default() -> Self {
+        EpisodePolicy {
+            max_steps: 5000,
+            stop_when_food_delivered: true,
+            no_progress_ticks: None,
+            stop_when_extinct: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec2;
+
+    use crate::episode::{EpisodePolicy, Termination};
+    use crate::Simulation;
+
+    #[test]
+    fn stops_early() {
+        let mut simulation = Simulation::zero();
+
+        let policy = EpisodePolicy {
+            max_steps: 100,
+            ..Default::default()
+        };
+        assert_eq!(simulation.run_episode(&policy), Termination::MaxSteps);
+        assert_eq!(simulation.stats().step_count, 100);
+
+        let policy = EpisodePolicy {
+            max_steps: 1000,
+            no_progress_ticks: Some(50),
+            ..Default::default()
+        };
+        assert_eq!(simulation.run_episode(&policy), Termination::NoProgress);
+        assert_eq!(simulation.stats().step_count, 100);
+
+        let ids = simulation.ants().ids().to_vec();
+        for id in ids {
+            simulation.despawn_ant(id);
+        }
+        simulation.spawn_ant(vec2(0., 0.), 0., 0);
+
+        let policy = EpisodePolicy::default();
+        assert_eq!(simulation.termination(&policy), None);
+
+        let id = simulation.ants().ids()[0];
+        simulation.despawn_ant(id);
+
+        assert_eq!(
+            simulation.termination(&policy),
+            Some(Termination::ColonyExtinct)
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use glam::Vec2;
+
+use crate::ants::AntId;
+use crate::pheromones::PheromoneChannel;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    FoodPickedUp {
+        ant: AntId,
+        pos: Vec2,
+    },
+    FoodDelivered {
+        ant: AntId,
+        nest: usize,
+        pos: Vec2,
+        count: usize,
+    },
+    PheromoneSpawned {
+        ant: AntId,
+        group: usize,
+        channel: PheromoneChannel,
+        pos: Vec2,
+    },
+    /// food dropped outside of the nest
+    FoodDropped {
+        ant: AntId,
+        pos: Vec2,
+        count: usize,
+    },
+    FoodHandedOver {
+        from: AntId,
+        to: AntId,
+        pos: Vec2,
+        count: usize,
+    },
+    /// the whole group got removed
+    PheromoneExpired {
+        group: usize,
+    },
+    /// the ant hit the border of the map and got turned around
+    AntBounced {
+        ant: AntId,
+        pos: Vec2,
+    },
+}
+
+/// queue of everything that happened since it was last drained
+///
+/// disabled by default so simulations nobody listens to don't pile up events
+#[derive(Default)]
+pub struct Events {
+    enabled: bool,
+    queue: Vec<Event>,
+}
+
+impl Events {
+    pub fn emit(&mut self, event: Event) {
+        if self.enabled {
+            self.queue.push(event);
+        }
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+
+        if !enabled {
+            self.queue.clear();
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn drain(&mut self) -> std::vec::Drain<'_, Event> {
+        self.queue.drain(..)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec2;
+
+    use crate::events::Event;
+    use crate::{Simulation, GAME_SIZE};
+
+    #[test]
+    fn emits_pick_up_and_bounce() {
+        let mut simulation = Simulation::zero();
+        simulation.set_events_enabled(true);
+
+        let gatherer = simulation.spawn_ant(vec2(300., 300.), 0., 0);
+        let bouncer = simulation.spawn_ant(vec2(GAME_SIZE, 0.), 0., 0);
+
+        simulation.step();
+
+        let events = simulation.drain_events().collect::<Vec<_>>();
+
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, Event::FoodPickedUp { ant, .. } if *ant == gatherer)));
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, Event::AntBounced { ant, .. } if *ant == bouncer)));
+
+        assert_eq!(simulation.drain_events().count(), 0);
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        let mut simulation = Simulation::zero();
+        simulation.spawn_ant(vec2(300., 300.), 0., 0);
+
+        simulation.step();
+
+        assert_eq!(simulation.drain_events().count(), 0);
+    }
+}
@@ -1,14 +1,186 @@
 use glam::Vec2;
 
+use crate::grid::Positioned;
+
 pub struct Food {
     pos: Vec2,
+    cached: bool,
 }
 
 impl Food {
     pub fn new(pos: Vec2) -> Food {
-        Food { pos }
+        Food { pos, cached: false }
+    }
+
+    /// food dropped by an ant outside of the nest
+    pub fn cached(pos: Vec2) -> Food {
+        Food { pos, cached: true }
     }
+
     pub fn pos(&self) -> &Vec2 {
         &self.pos
     }
+
+    pub fn is_cached(&self) -> bool {
+        self.cached
+    }
+}
+
+impl Positioned for Food {
+    fn position(&self) -> Vec2 {
+        self.pos
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec2;
+
+    use crate::ants::Ants;
+    use crate::food::Food;
+    use crate::math::ray_inserect_circle;
+    use crate::scenario::Scenario;
+    use crate::{Simulation, FOOD_SIZE};
+
+    #[test]
+    fn hands_over_before_caching() {
+        let scenario = Scenario {
+            food_handover: true,
+            ..Default::default()
+        };
+        let mut simulation = Simulation::with_scenario(scenario, vec![Default::default()]);
+
+        let giver = simulation.spawn_ant(vec2(-200., -200.), 0., 0);
+        let receiver = simulation.spawn_ant(vec2(-195., -200.), 0., 0);
+        let loner = simulation.spawn_ant(vec2(200., -200.), 0., 0);
+
+        for id in [giver, loner] {
+            let index = simulation.ants.index_of(id).unwrap();
+            simulation.ants.carried_foods[index] = 1;
+            simulation.ants.drops_food[index] = true;
+        }
+
+        Simulation::hand_over_food(
+            &mut simulation.ants,
+            &simulation.castes,
+            &mut simulation.ant_grid,
+            &mut simulation.profiler,
+            &mut simulation.stats,
+            &mut simulation.events,
+        );
+        Simulation::cache_food(
+            &mut simulation.ants,
+            &mut simulation.foods,
+            &mut simulation.profiler,
+            &mut simulation.stats,
+            &mut simulation.events,
+        );
+
+        let carried = |id| simulation.ants.get(id).unwrap().carried_food();
+        assert_eq!(carried(giver), 0);
+        assert_eq!(carried(receiver), 1);
+        assert_eq!(carried(loner), 0);
+
+        assert_eq!(simulation.stats.handed_over_food, 1);
+        assert_eq!(simulation.stats.cached_food, 1);
+        assert_eq!(
+            simulation
+                .foods()
+                .iter()
+                .filter(|food| food.is_cached())
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn caches_stay_on_the_ground() {
+        let mut simulation = Simulation::zero();
+
+        let id = simulation.spawn_ant(vec2(-200., -200.), 0., 0);
+        let index = simulation.ants.index_of(id).unwrap();
+        simulation.ants.carried_foods[index] = 1;
+        simulation.ants.drops_food[index] = true;
+
+        for _ in 0..10 {
+            Simulation::pick_up_food(
+                &mut simulation.ants,
+                &simulation.castes,
+                &mut simulation.foods,
+                &mut simulation.near_food,
+                &mut simulation.profiler,
+                &mut simulation.stats,
+                &mut simulation.events,
+            );
+            Simulation::cache_food(
+                &mut simulation.ants,
+                &mut simulation.foods,
+                &mut simulation.profiler,
+                &mut simulation.stats,
+                &mut simulation.events,
+            );
+        }
+
+        assert_eq!(simulation.ants.get(id).unwrap().carried_food(), 0);
+        assert_eq!(simulation.stats.cached_food, 1);
+        assert_eq!(
+            simulation
+                .foods()
+                .iter()
+                .filter(|food| food.is_cached())
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn rays_match_brute_force() {
+        let mut simulation = Simulation::zero();
+
+        // some scattered food next to the big patch
+        for i in 0..200 {
+            let pos = vec2((i * 53 % 400) as f32 + 50., (i * 97 % 400) as f32 - 100.);
+            simulation.foods.insert(&pos, Food::cached(pos));
+        }
+
+        for i in 0..500 {
+            let pos = vec2((i * 37 % 500) as f32 - 50., (i * 71 % 500) as f32 - 50.);
+            simulation.spawn_ant(pos, i as f32 * 0.7, 0);
+        }
+
+        let see_distance = simulation.castes[0].see_distance;
+
+        Simulation::see_food(
+            &mut simulation.ants,
+            &simulation.castes,
+            &simulation.foods,
+            1.,
+            &mut simulation.profiler,
+        );
+
+        let foods = simulation.foods();
+        let mut hits = 0;
+
+        for ant in simulation.ants().iter() {
+            let directions = Ants::ray_directions(ant.dir());
+
+            for (ray, direction) in ant.rays().iter().zip(directions) {
+                let expected = foods
+                    .iter()
+                    .filter(|food| {
+                        food.pos().distance_squared(ant.pos()) <= see_distance * see_distance
+                    })
+                    .filter_map(|food| {
+                        ray_inserect_circle(*food.pos(), FOOD_SIZE, ant.pos(), direction)
+                    })
+                    .min_by(|a, b| a.total_cmp(b))
+                    .unwrap_or(-1.);
+
+                assert_eq!(*ray, expected);
+                hits += (expected >= 0.) as usize;
+            }
+        }
+
+        assert!(hits > 100);
+    }
 }
@@ -1,6 +1,10 @@
 use crate::math::circle_intersects_rect;
 use glam::{vec2, Vec2};
-use itertools::Itertools;
+
+/// items that know where they are, needed for the exact queries
+pub trait Positioned {
+    fn position(&self) -> Vec2;
+}
 
 pub struct Grid<T> {
     data: Vec<Vec<T>>,
@@ -48,35 +52,50 @@ impl<T> Grid<T> {
         Self: Sized,
         F: FnMut(&mut Vec<T>),
     {
-        let width_per_tile = self.width_per_tile;
-
-        let pos_index = (pos + self.width) / width_per_tile;
-        let radius_offset = radius / width_per_tile;
-
-        let min_x = ((pos_index.x - radius_offset).floor() as usize).max(0);
-        let max_x = ((pos_index.x + radius_offset).ceil() as usize).min(self.size - 1);
-
-        let min_y = ((pos_index.y - radius_offset).floor() as usize).max(0);
-        let max_y = ((pos_index.y + radius_offset).ceil() as usize).min(self.size - 1);
+        let Some((min_x, max_x, min_y, max_y)) = self.cell_bounds(pos, radius) else {
+            return;
+        };
 
         for x in min_x..=max_x {
             for y in min_y..=max_y {
-                let data = &mut self.data[y * self.size + x];
-
-                if data.is_empty() {
+                if self.data[y * self.size + x].is_empty() || !self.touches(x, y, pos, radius) {
                     continue;
                 }
 
-                let tile_x = x as f32 * width_per_tile + self.tile_center_offset;
-                let tile_y = y as f32 * width_per_tile + self.tile_center_offset;
-
-                if circle_intersects_rect(pos, radius, vec2(tile_x, tile_y), width_per_tile) {
-                    f(data);
-                }
+                f(&mut self.data[y * self.size + x]);
             }
         }
     }
 
+    /// min x, max x, min y, max y of the cells around the circle, none if it is outside of the grid
+    fn cell_bounds(&self, pos: Vec2, radius: f32) -> Option<(usize, usize, usize, usize)> {
+        let pos_index = (pos + self.width) / self.width_per_tile;
+        let radius_offset = radius / self.width_per_tile;
+
+        let min = pos_index - radius_offset;
+        let max = pos_index + radius_offset;
+
+        if max.x < 0. || max.y < 0. || min.x >= self.size as f32 || min.y >= self.size as f32 {
+            return None;
+        }
+
+        // casting saturates, so negative mins end up at 0
+        let min_x = min.x.floor() as usize;
+        let max_x = (max.x.ceil() as usize).min(self.size - 1);
+
+        let min_y = min.y.floor() as usize;
+        let max_y = (max.y.ceil() as usize).min(self.size - 1);
+
+        Some((min_x, max_x, min_y, max_y))
+    }
+
+    fn touches(&self, x: usize, y: usize, pos: Vec2, radius: f32) -> bool {
+        let tile_x = x as f32 * self.width_per_tile + self.tile_center_offset;
+        let tile_y = y as f32 * self.width_per_tile + self.tile_center_offset;
+
+        circle_intersects_rect(pos, radius, vec2(tile_x, tile_y), self.width_per_tile)
+    }
+
     pub fn retain<F>(&mut self, f: F)
     where
         Self: Sized,
@@ -87,8 +106,24 @@ impl<T> Grid<T> {
             .for_each(|data| data.retain(|item| f(item)));
     }
 
-    pub fn all(&self) -> Vec<&T> {
-        self.data.iter().flatten().collect_vec()
+    pub fn clear(&mut self) {
+        self.data.iter_mut().for_each(|data| data.clear());
+    }
+
+    /// every cell with its (x, y) index
+    pub fn cells(&self) -> impl Iterator<Item = ((usize, usize), &Vec<T>)> {
+        self.data
+            .iter()
+            .enumerate()
+            .map(|(index, data)| ((index % self.size, index / self.size), data))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.iter().all(|data| data.is_empty())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.data.iter().flatten()
     }
 
     pub fn insert(&mut self, pos: &Vec2, val: T) {
@@ -103,6 +138,471 @@ impl<T> Grid<T> {
         let x = ((pos.x + self.width) / width_per_tile).floor() as usize;
         let y = ((pos.y + self.width) / width_per_tile).floor() as usize;
 
-        (x.max(0).min(self.size - 1), y.max(0).min(self.size - 1))
+        (x.min(self.size - 1), y.min(self.size - 1))
+    }
+
+    /// cells crossed by the ray in the order they are entered, with the distance they are entered at
+    ///
+    /// starts in the cell of `origin`, `dir` has to be normalized
+    pub fn ray_cells(&self, origin: Vec2, dir: Vec2, max_distance: f32) -> RayCells {
+        let (x, y) = self.indexes_from_pos(&origin);
+        let cell = (origin + self.width) / self.width_per_tile;
+
+        // distance to the first cell border and between two borders per axis
+        let first_border = |cell: f32, index: usize, dir: f32| {
+            if dir > 0. {
+                (index as f32 + 1. - cell) * self.width_per_tile / dir
+            } else if dir < 0. {
+                (cell - index as f32) * self.width_per_tile / -dir
+            } else {
+                f32::INFINITY
+            }
+        };
+
+        RayCells {
+            x: x as isize,
+            y: y as isize,
+            step_x: if dir.x < 0. { -1 } else { 1 },
+            step_y: if dir.y < 0. { -1 } else { 1 },
+            next_x: first_border(cell.x, x, dir.x),
+            next_y: first_border(cell.y, y, dir.y),
+            delta_x: self.width_per_tile / dir.x.abs(),
+            delta_y: self.width_per_tile / dir.y.abs(),
+            distance: 0.,
+            max_distance,
+            size: self.size as isize,
+        }
+    }
+}
+
+impl<T: Positioned> Grid<T> {
+    /// every item closer than `radius` to `pos`
+    pub fn within(&self, pos: Vec2, radius: f32) -> Within<'_, T> {
+        // starting below the last row leaves no cell to look at
+        let (min_x, max_x, min_y, max_y) = self.cell_bounds(pos, radius).unwrap_or((0, 0, 1, 0));
+
+        Within {
+            grid: self,
+            pos,
+            radius,
+            radius_squared: radius * radius,
+            min_x,
+            max_x,
+            max_y,
+            x: min_x,
+            y: min_y,
+            items: [].iter(),
+        }
+    }
+
+    /// up to `k` items sorted by their distance to `pos`
+    pub fn nearest(&self, pos: Vec2, k: usize) -> Vec<&T> {
+        let mut found: Vec<(f32, &T)> = vec![];
+
+        if k == 0 {
+            return vec![];
+        }
+
+        let (center_x, center_y) = self.indexes_from_pos(&pos);
+
+        // rings of cells around the cell of `pos`, ring n is at least n - 1 cells away
+        for ring in 0..self.size {
+            let min_x = center_x.saturating_sub(ring);
+            let max_x = (center_x + ring).min(self.size - 1);
+            let min_y = center_y.saturating_sub(ring);
+            let max_y = (center_y + ring).min(self.size - 1);
+
+            for y in min_y..=max_y {
+                for x in min_x..=max_x {
+                    if x.abs_diff(center_x) != ring && y.abs_diff(center_y) != ring {
+                        continue;
+                    }
+
+                    for item in &self.data[y * self.size + x] {
+                        found.push((item.position().distance_squared(pos), item));
+                    }
+                }
+            }
+
+            found.sort_by(|a, b| a.0.total_cmp(&b.0));
+            found.truncate(k);
+
+            let checked_distance = ring as f32 * self.width_per_tile;
+            if found.len() == k && found[k - 1].0 <= checked_distance * checked_distance {
+                break;
+            }
+        }
+
+        found.into_iter().map(|(_, item)| item).collect()
+    }
+
+    /// every item inside the rectangle spanned by `min` and `max`
+    pub fn in_rect(&self, min: Vec2, max: Vec2) -> impl Iterator<Item = &T> {
+        let (min_x, min_y) = self.indexes_from_pos(&min);
+        let (max_x, max_y) = self.indexes_from_pos(&max);
+
+        (min_y..=max_y)
+            .flat_map(move |y| (min_x..=max_x).map(move |x| &self.data[y * self.size + x]))
+            .flatten()
+            .filter(move |item| {
+                let pos = item.position();
+                pos.cmpge(min).all() && pos.cmple(max).all()
+            })
+    }
+
+    /// nearest item hit by the ray closer than `max_distance` with the distance it got hit at
+    ///
+    /// `hit` returns where along the ray an item gets hit, items can't be bigger than `item_radius`.
+    /// walks the cells along the ray and stops as soon as no later cell can hold a closer hit
+    pub fn cast_ray<F>(
+        &self,
+        origin: Vec2,
+        dir: Vec2,
+        max_distance: f32,
+        item_radius: f32,
+        mut hit: F,
+    ) -> Option<(f32, &T)>
+    where
+        F: FnMut(&T) -> Option<f32>,
+    {
+        // items sticking into the ray can be stored in the cells next to it
+        let reach = (item_radius / self.width_per_tile).ceil() as usize;
+
+        let mut nearest: Option<(f32, &T)> = None;
+        let mut previous: Option<(usize, usize)> = None;
+
+        for (entry, (x, y)) in self.ray_cells(origin, dir, max_distance + item_radius) {
+            if nearest.is_some_and(|(distance, _)| entry > distance + item_radius) {
+                break;
+            }
+
+            for cell_y in y.saturating_sub(reach)..=(y + reach).min(self.size - 1) {
+                for cell_x in x.saturating_sub(reach)..=(x + reach).min(self.size - 1) {
+                    // the walk only goes into one direction per axis, so every cell next to
+                    // the previous one has already been checked
+                    if previous.is_some_and(|(previous_x, previous_y)| {
+                        cell_x.abs_diff(previous_x) <= reach && cell_y.abs_diff(previous_y) <= reach
+                    }) {
+                        continue;
+                    }
+
+                    for item in &self.data[cell_y * self.size + cell_x] {
+                        let Some(distance) = hit(item) else {
+                            continue;
+                        };
+
+                        if distance <= max_distance
+                            && nearest.is_none_or(|(nearest, _)| distance < nearest)
+                        {
+                            nearest = Some((distance, item));
+                        }
+                    }
+                }
+            }
+
+            previous = Some((x, y));
+        }
+
+        nearest
+    }
+
+    /// removes the first item closer than `radius` to `pos` accepted by `f`
+    ///
+    /// walks the cells column by column like `for_each`, so the same item is found first
+    pub fn remove_within<F>(&mut self, pos: Vec2, radius: f32, mut f: F) -> Option<T>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let (min_x, max_x, min_y, max_y) = self.cell_bounds(pos, radius)?;
+
+        for x in min_x..=max_x {
+            for y in min_y..=max_y {
+                let data = &mut self.data[y * self.size + x];
+
+                let found = data.iter().position(|item| {
+                    item.position().distance_squared(pos) < radius * radius && f(item)
+                });
+
+                if let Some(index) = found {
+                    return Some(data.remove(index));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// runs `update` on the first item at `old_pos` accepted by `f` and moves it into the cell of its new position
+    ///
+    /// returns false if there is no such item
+    pub fn update_position<F, U>(&mut self, old_pos: Vec2, f: F, update: U) -> bool
+    where
+        F: FnMut(&T) -> bool,
+        U: FnOnce(&mut T),
+    {
+        let (x, y) = self.indexes_from_pos(&old_pos);
+        let data = &mut self.data[y * self.size + x];
+
+        let Some(index) = data.iter().position(f) else {
+            return false;
+        };
+
+        update(&mut data[index]);
+
+        let new_pos = data[index].position();
+        if self.indexes_from_pos(&new_pos) != (x, y) {
+            let item = self.data[y * self.size + x].remove(index);
+            self.insert(&new_pos, item);
+        }
+
+        true
+    }
+}
+
+/// iterator returned by `Grid::within`
+pub struct Within<'a, T> {
+    grid: &'a Grid<T>,
+    pos: Vec2,
+    radius: f32,
+    radius_squared: f32,
+    min_x: usize,
+    max_x: usize,
+    max_y: usize,
+    // next cell to look at
+    x: usize,
+    y: usize,
+    items: std::slice::Iter<'a, T>,
+}
+
+impl<'a, T: Positioned> Within<'a, T> {
+    /// moves on to the next cell touching the circle, false once there is none left
+    fn next_cell(&mut self) -> bool {
+        while self.y <= self.max_y {
+            let (x, y) = (self.x, self.y);
+
+            if self.x == self.max_x {
+                self.x = self.min_x;
+                self.y += 1;
+            } else {
+                self.x += 1;
+            }
+
+            let data = &self.grid.data[y * self.grid.size + x];
+            if !data.is_empty() && self.grid.touches(x, y, self.pos, self.radius) {
+                self.items = data.iter();
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+impl<'a, T: Positioned> Iterator for Within<'a, T> {
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            for item in self.items.by_ref() {
+                if item.position().distance_squared(self.pos) <= self.radius_squared {
+                    return Some(item);
+                }
+            }
+
+            if !self.next_cell() {
+                return None;
+            }
+        }
+    }
+}
+
+/// iterator returned by `Grid::ray_cells`, yields the distance a cell is entered at and its (x, y) index
+pub struct RayCells {
+    x: isize,
+    y: isize,
+    step_x: isize,
+    step_y: isize,
+    // distance at which the next x / y border gets crossed
+    next_x: f32,
+    next_y: f32,
+    // distance between two x / y borders
+    delta_x: f32,
+    delta_y: f32,
+    distance: f32,
+    max_distance: f32,
+    size: isize,
+}
+
+impl Iterator for RayCells {
+    type Item = (f32, (usize, usize));
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.distance > self.max_distance
+            || !(0..self.size).contains(&self.x)
+            || !(0..self.size).contains(&self.y)
+        {
+            return None;
+        }
+
+        let cell = (self.distance, (self.x as usize, self.y as usize));
+
+        if self.next_x < self.next_y {
+            self.distance = self.next_x;
+            self.next_x += self.delta_x;
+            self.x += self.step_x;
+        } else {
+            self.distance = self.next_y;
+            self.next_y += self.delta_y;
+            self.y += self.step_y;
+        }
+
+        Some(cell)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{vec2, Vec2};
+    use itertools::Itertools;
+
+    use crate::grid::{Grid, Positioned};
+    use crate::math::ray_inserect_circle;
+
+    impl Positioned for Vec2 {
+        fn position(&self) -> Vec2 {
+            *self
+        }
+    }
+
+    fn scattered() -> (Grid<Vec2>, Vec<Vec2>) {
+        let mut grid = Grid::new(25, 500.);
+        let mut positions = vec![];
+
+        for i in 0..500 {
+            // deterministic but spread over the whole grid
+            let pos = vec2(
+                (i as f32 * 37.1) % 1000. - 500.,
+                (i as f32 * 91.7) % 1000. - 500.,
+            );
+            grid.insert(&pos, pos);
+            positions.push(pos);
+        }
+
+        (grid, positions)
+    }
+
+    fn sorted(mut positions: Vec<Vec2>) -> Vec<Vec2> {
+        positions.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+        positions
+    }
+
+    #[test]
+    fn queries_match_brute_force() {
+        let (grid, positions) = scattered();
+        let center = vec2(30., -120.);
+
+        let within = grid.within(center, 90.).copied().collect_vec();
+        let expected = positions
+            .iter()
+            .copied()
+            .filter(|pos| pos.distance(center) <= 90.)
+            .collect_vec();
+        assert_eq!(sorted(within), sorted(expected));
+
+        let nearest = grid.nearest(center, 5).into_iter().copied().collect_vec();
+        let expected = positions
+            .iter()
+            .copied()
+            .sorted_by(|a, b| a.distance(center).total_cmp(&b.distance(center)))
+            .take(5)
+            .collect_vec();
+        assert_eq!(nearest, expected);
+
+        let (min, max) = (vec2(-200., -50.), vec2(120., 300.));
+        let in_rect = grid.in_rect(min, max).copied().collect_vec();
+        let expected = positions
+            .iter()
+            .copied()
+            .filter(|pos| pos.cmpge(min).all() && pos.cmple(max).all())
+            .collect_vec();
+        assert_eq!(sorted(in_rect), sorted(expected));
+
+        // items bigger than a cell have to be found in the cells next to the ray too
+        for angle in 0..36 {
+            let dir = Vec2::from_angle(angle as f32 * 10f32.to_radians());
+            let hit = |pos: &Vec2| ray_inserect_circle(*pos, 50., center, dir);
+
+            let nearest = grid.cast_ray(center, dir, 300., 50., hit);
+            let expected = positions
+                .iter()
+                .filter_map(|pos| hit(pos).filter(|distance| *distance <= 300.))
+                .min_by(|a, b| a.total_cmp(b));
+            assert_eq!(nearest.map(|(distance, _)| distance), expected);
+        }
+    }
+
+    #[test]
+    fn ray_cells_walk_in_order() {
+        let grid: Grid<Vec2> = Grid::new(25, 500.);
+        let dir = vec2(1., 0.5).normalize();
+
+        let cells = grid.ray_cells(vec2(5., 5.), dir, 200.).collect_vec();
+
+        assert_eq!(cells[0], (0., grid.indexes_from_pos(&vec2(5., 5.))));
+        assert!(cells.iter().tuple_windows().all(|(a, b)| {
+            a.0 <= b.0 && a.1 .0.abs_diff(b.1 .0) + a.1 .1.abs_diff(b.1 .1) == 1
+        }));
+
+        // every point along the ray lies in one of the cells
+        for step in 0..=200 {
+            let cell = grid.indexes_from_pos(&(vec2(5., 5.) + dir * step as f32));
+            assert!(cells.iter().any(|(_, walked)| *walked == cell));
+        }
+    }
+
+    #[test]
+    fn moves_and_removes() {
+        let (mut grid, _) = scattered();
+        let old = vec2(30., -120.);
+        grid.insert(&old, old);
+
+        let new = vec2(-400., 400.);
+        assert!(grid.update_position(old, |pos| *pos == old, |pos| *pos = new));
+        assert!(grid.within(old, 0.1).all(|pos| *pos != old));
+        assert_eq!(grid.nearest(new, 1), vec![&new]);
+
+        assert_eq!(grid.remove_within(new, 0.1, |_| true), Some(new));
+        assert_eq!(grid.remove_within(new, 0.1, |_| true), None);
+    }
+
+    #[test]
+    fn queries_outside_of_the_grid() {
+        let (mut grid, positions) = scattered();
+
+        for far in [
+            vec2(1e6, 0.),
+            vec2(0., 1e6),
+            vec2(-1e6, -1e6),
+            vec2(5000., 5000.),
+        ] {
+            assert_eq!(grid.within(far, 100.).count(), 0);
+            assert_eq!(grid.remove_within(far, 100., |_| true), None);
+
+            let mut visited = 0;
+            grid.for_each(far, 100., |_| visited += 1);
+            assert_eq!(visited, 0);
+        }
+
+        // circles sticking into the grid from outside still find what they cover
+        let center = vec2(600., 0.);
+        let within = grid.within(center, 150.).copied().collect_vec();
+        let expected = positions
+            .iter()
+            .copied()
+            .filter(|pos| pos.distance(center) <= 150.)
+            .collect_vec();
+        assert!(!expected.is_empty());
+        assert_eq!(sorted(within), sorted(expected));
     }
 }
@@ -0,0 +1,205 @@
+use std::error::Error;
+use std::f32::consts::PI;
+use std::fmt::{Display, Formatter};
+
+use glam::Vec2;
+
+use crate::ants::AntId;
+use crate::{Simulation, GAME_SIZE};
+
+/// a broken invariant found after the given tick
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvariantError {
+    pub tick: usize,
+    pub violation: Violation,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    /// food stored in a grid cell not matching its position
+    MisplacedFood {
+        pos: Vec2,
+        cell: (usize, usize),
+        expected: (usize, usize),
+    },
+    /// the pheromone grid points to a removed group or past the end of a group
+    DeadPheromone { group: usize, index: usize },
+    /// a column of `Ants` doesn't have one entry per ant
+    ColumnLength {
+        column: &'static str,
+        len: usize,
+        expected: usize,
+    },
+    /// not finite or outside of the map
+    InvalidPosition { ant: AntId, pos: Vec2 },
+    /// not finite or more than a full turn
+    InvalidDir { ant: AntId, dir: f32 },
+}
+
+impl Display for InvariantError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invariant broken after tick {}: ", self.tick)?;
+
+        match &self.violation {
+            Violation::MisplacedFood {
+                pos,
+                cell,
+                expected,
+            } => write!(
+                f,
+                "food at {} is stored in cell {:?} instead of {:?}",
+                pos, cell, expected
+            ),
+            Violation::DeadPheromone { group, index } => write!(
+                f,
+                "pheromone grid refers to pheromone {} of group {} which does not exist",
+                index, group
+            ),
+            Violation::ColumnLength {
+                column,
+                len,
+                expected,
+            } => write!(
+                f,
+                "ant column {} has {} entries but there are {} ants",
+                column, len, expected
+            ),
+            Violation::InvalidPosition { ant, pos } => write!(
+                f,
+                "ant {} is at {} which is not finite or outside of the map",
+                ant.raw(),
+                pos
+            ),
+            Violation::InvalidDir { ant, dir } => write!(
+                f,
+                "ant {} has the direction {} which is not finite or not normalised",
+                ant.raw(),
+                dir
+            ),
+        }
+    }
+}
+
+impl Error for InvariantError {}
+
+impl Simulation {
+    /// enables checking all invariants after every step, panicking on the first broken one
+    pub fn set_validation_enabled(&mut self, enabled: bool) {
+        self.validate = enabled;
+    }
+
+    /// the first broken invariant, costs a pass over every ant, food and pheromone
+    pub fn check_invariants(&self) -> Result<(), InvariantError> {
+        self.find_violation().map_or(Ok(()), |violation| {
+            Err(InvariantError {
+                tick: self.stats.step_count,
+                violation,
+            })
+        })
+    }
+
+    fn find_violation(&self) -> Option<Violation> {
+        for (cell, foods) in self.foods.cells() {
+            for food in foods {
+                let expected = self.foods.indexes_from_pos(food.pos());
+
+                if cell != expected {
+                    return Some(Violation::MisplacedFood {
+                        pos: *food.pos(),
+                        cell,
+                        expected,
+                    });
+                }
+            }
+        }
+
+        for (_, pheromones) in self.pheromones.grid.cells() {
+            for (group, index) in pheromones {
+                let alive = self
+                    .pheromones
+                    .groups
+                    .get(*group)
+                    .and_then(|group| group.as_ref())
+                    .is_some_and(|group| *index < group.positions.len());
+
+                if !alive {
+                    return Some(Violation::DeadPheromone {
+                        group: *group,
+                        index: *index,
+                    });
+                }
+            }
+        }
+
+        let expected = self.ants.len();
+        for (column, len) in self.ants.column_lengths() {
+            if len != expected {
+                return Some(Violation::ColumnLength {
+                    column,
+                    len,
+                    expected,
+                });
+            }
+        }
+
+        for ((id, pos), dir) in self
+            .ants
+            .ids
+            .iter()
+            .zip(&self.ants.positions)
+            .zip(&self.ants.dirs)
+        {
+            if !pos.is_finite() || pos.abs().max_element() > GAME_SIZE {
+                return Some(Violation::InvalidPosition {
+                    ant: *id,
+                    pos: *pos,
+                });
+            }
+
+            if !dir.is_finite() || dir.abs() >= PI * 2. {
+                return Some(Violation::InvalidDir {
+                    ant: *id,
+                    dir: *dir,
+                });
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec2;
+
+    use crate::invariants::Violation;
+    use crate::Simulation;
+
+    #[test]
+    fn reports_broken_invariants() {
+        let mut simulation = Simulation::zero();
+        simulation.set_validation_enabled(true);
+
+        for _ in 0..100 {
+            simulation.step();
+        }
+
+        let id = simulation.spawn_ant(vec2(0., 0.), 0., 0);
+        let index = simulation.ants.index_of(id).unwrap();
+        simulation.ants.positions[index] = vec2(f32::NAN, 0.);
+
+        let error = simulation.check_invariants().unwrap_err();
+        assert_eq!(error.tick, 100);
+        assert!(matches!(error.violation, Violation::InvalidPosition { ant, .. } if ant == id));
+        assert!(error.to_string().contains("tick 100"));
+
+        simulation.ants.positions[index] = vec2(0., 0.);
+        simulation.ants.rays.pop();
+
+        let error = simulation.check_invariants().unwrap_err();
+        assert!(matches!(
+            error.violation,
+            Violation::ColumnLength { column: "rays", .. }
+        ));
+    }
+}
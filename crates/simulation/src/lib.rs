@@ -1,51 +1,84 @@
-use std::{cell::OnceCell, f32::consts::PI};
+use std::{cell::RefCell, f32::consts::PI};
 
+use crate::castes::{assign_castes, Caste};
+use crate::episode::{EpisodePolicy, Termination};
+use crate::events::{Event, Events};
 use crate::food::Food;
 use crate::grid::Grid;
-use crate::timings::Timings;
-use ants::{Ants, ANT_PICK_UP_DISTANCE, ANT_RAY_COUNT, ANT_SEE_DISTANCE};
+use crate::parallel::for_each_chunk;
+use crate::pheromones::{
+    PheromoneChannel, PheromoneGroup, Pheromones, PHEROMONE_CHANNEL_COUNT, PHEROMONE_MIN_STRENGTH,
+};
+use crate::scenario::{Scenario, Wind};
+use crate::scent::{Scent, SCENT_UPDATE_TICKS};
+use crate::stats::Stats;
+use crate::timings::profiler::Profiler;
+use ants::{
+    AntId, Ants, ANT_COLLISION_RADIUS, ANT_CROWDING_SLOWDOWN, ANT_PICK_UP_DISTANCE, ANT_RAY_COUNT,
+};
 use glam::{vec2, Vec2};
 use itertools::Itertools;
-use math::ray_inserect_circle;
+use math::{hash_unit, ray_inserect_circle};
 use neural_network::NeuralNetwork;
-use std::time::Instant;
 
 mod food;
 mod grid;
 mod math;
+pub mod stats;
 pub mod timings;
 
 pub mod ants;
+pub mod castes;
+pub mod episode;
+pub mod events;
+pub mod invariants;
+pub mod palette;
+mod parallel;
+pub mod pheromones;
+pub mod recorder;
+pub mod scenario;
+pub mod scent;
 
 const TICKS_UNTIL_PHEROMONE: usize = 10;
 pub const ANT_HILL_RADIUS: f32 = 50.;
 pub const GAME_SIZE: f32 = 500.;
 pub const FOOD_SIZE: f32 = 7.;
 
-pub const NEURAL_NETWORK_INPUT_SIZE: usize = 5 + ANT_RAY_COUNT;
-pub const NEURAL_NETWORK_OUTPUT_SIZE: usize = 4;
+thread_local! {
+    // reused for every ant so running the networks doesn't allocate
+    static NETWORK_BUFFER: RefCell<Vec<f32>> = const { RefCell::new(Vec::new()) };
+}
+
+/// position, direction, target direction, load, time of day, rays, pheromones and scent
+pub const NEURAL_NETWORK_INPUT_SIZE: usize = 9 + ANT_RAY_COUNT + PHEROMONE_CHANNEL_COUNT * 2;
+/// turning, the deposit intensity per pheromone channel and dropping food
+pub const NEURAL_NETWORK_OUTPUT_SIZE: usize = 2 + PHEROMONE_CHANNEL_COUNT;
+/// output above which an ant drops its food
+const DROP_FOOD_THRESHOLD: f32 = 0.5;
 
 pub struct Simulation {
     ants: Ants,
     pheromones: Pheromones,
     foods: Grid<Food>,
+    scent: Scent,
+    /// ant indices, rebuilt every tick when collision is enabled and before food gets handed over
+    ant_grid: Grid<usize>,
+
+    // reused every tick to keep the hot path free of allocations
+    collision_pushes: Vec<Vec2>,
+    near_food: Vec<bool>,
 
     ticks_until_pheromone: usize,
-    timings: Timings,
+    profiler: Profiler,
     stats: Stats,
+    events: Events,
+    /// checks the invariants after every step
+    validate: bool,
 
-    neural_network: NeuralNetwork,
+    scenario: Scenario,
+    castes: Vec<Caste>,
 }
 
-pub struct Pheromones {
-    //todo dont all pub
-    pub grid: Grid<usize>,
-    pub positions: Vec<Vec2>,
-    pub colors: Vec<(f32, f32, f32)>,
-
-    // size per pheromone group, None marking a deleted section
-    pub sizes: Vec<Option<f32>>,
-}
 impl Default for Simulation {
     fn default() -> Self {
         Simulation::new(NeuralNetwork::new(
@@ -55,12 +88,6 @@ impl Default for Simulation {
     }
 }
 
-pub struct Stats {
-    pub step_count: usize,
-    pub picked_up_food: usize,
-    pub dropped_of_food: usize,
-}
-
 impl Simulation {
     pub fn zero() -> Self {
         Simulation::new(NeuralNetwork::zero(
@@ -70,41 +97,94 @@ impl Simulation {
     }
 
     pub fn new(neural_network: NeuralNetwork) -> Simulation {
-        assert_eq!(
-            neural_network.get_input_size(),
-            NEURAL_NETWORK_INPUT_SIZE,
-            "Neural-network has wrong input size"
-        );
-        assert_eq!(
-            neural_network.get_output_size(),
-            NEURAL_NETWORK_OUTPUT_SIZE,
-            "Neural-network has wrong output size"
+        Simulation::with_castes(vec![Caste::new(castes::DEFAULT_CASTE_NAME, neural_network)])
+    }
+
+    pub fn with_castes(castes: Vec<Caste>) -> Simulation {
+        Simulation::with_scenario(Scenario::default(), castes)
+    }
+
+    pub fn with_scenario(scenario: Scenario, mut castes: Vec<Caste>) -> Simulation {
+        assert!(!castes.is_empty(), "Colony needs at least one caste");
+
+        for caste in &mut castes {
+            assert_eq!(
+                caste.neural_network.get_input_size(),
+                NEURAL_NETWORK_INPUT_SIZE,
+                "Neural-network of caste {} has wrong input size",
+                caste.name
+            );
+            assert_eq!(
+                caste.neural_network.get_output_size(),
+                NEURAL_NETWORK_OUTPUT_SIZE,
+                "Neural-network of caste {} has wrong output size",
+                caste.name
+            );
+            assert!(
+                caste.carry_capacity > 0,
+                "Caste {} needs to carry at least one food",
+                caste.name
+            );
+            assert!(
+                caste.ratio > 0. && caste.ratio.is_finite(),
+                "Caste {} needs a positive ratio",
+                caste.name
+            );
+
+            caste.neural_network.build();
+        }
+
+        assert!(
+            !scenario.nests.is_empty(),
+            "Scenario needs at least one nest"
         );
 
-        let mut ants = Ants {
-            positions: vec![],
-            dirs: vec![],
-            target_dirs: vec![],
-            caries_foods: vec![],
-            pheromone_colors: vec![],
-            rays: vec![],
-        };
+        if let Some(day_night) = &scenario.day_night {
+            assert!(day_night.length > 0, "Days need at least one tick");
+            assert!(
+                (0. ..=1.).contains(&day_night.night_see_distance)
+                    && (0. ..=1.).contains(&day_night.night_evaporation),
+                "Night see distance and evaporation need to be between 0 and 1"
+            );
+        }
 
-        const ANTS_TO_SPAWN: usize = 200;
-        const ANGLE_PER_ANT: f32 = PI * 2. / ANTS_TO_SPAWN as f32;
+        if let Some(wind) = &scenario.wind {
+            assert!(
+                wind.swirl_size > 0. && wind.swirl_size.is_finite(),
+                "Wind swirls need a positive size"
+            );
+            assert!(
+                wind.base.is_finite() && wind.swirl.is_finite() && wind.ant_drift.is_finite(),
+                "Wind needs a finite strength"
+            );
+        }
 
-        for i in 0..ANTS_TO_SPAWN {
-            let direction = ANGLE_PER_ANT * i as f32;
+        let mut ants = Ants::default();
+        let mut stats = Stats::default();
 
-            ants.positions.push(Vec2::ZERO);
-            ants.dirs.push(direction);
-            ants.target_dirs.push(direction);
-            ants.caries_foods.push(false);
-            ants.pheromone_colors.push((0.0, 0.0, 0.0));
-            ants.rays.push(vec![0.; ANT_RAY_COUNT]);
+        for _ in &scenario.nests {
+            stats.add_nest();
         }
 
-        let mut foods = Grid::new(25, GAME_SIZE);
+        let angle_per_ant = PI * 2. / scenario.ant_count as f32;
+
+        for (i, caste) in assign_castes(&castes, scenario.ant_count)
+            .into_iter()
+            .enumerate()
+        {
+            let direction = if scenario.seed == 0 {
+                angle_per_ant * i as f32
+            } else {
+                hash_unit(scenario.seed, i as u64) * PI * 2.
+            };
+            let nest = scenario.nests[i % scenario.nests.len()];
+
+            let id = ants.spawn(nest, direction, caste);
+            stats.add_ant(id, nest);
+        }
+
+        // cells about as big as a food keep the ray walks through dense patches short
+        let mut foods = Grid::new(100, GAME_SIZE);
 
         for x in 0..50 {
             for y in 0..50 {
@@ -115,197 +195,550 @@ impl Simulation {
 
         Simulation {
             ants,
-            pheromones: Pheromones {
-                grid: Grid::new(25, GAME_SIZE),
-                positions: vec![],
-                sizes: vec![],
-                colors: vec![],
-            },
+            pheromones: Pheromones::new(GAME_SIZE),
+            scent: Scent::new(GAME_SIZE),
             foods,
+            ant_grid: Grid::new(100, GAME_SIZE),
+            collision_pushes: vec![],
+            near_food: vec![],
             ticks_until_pheromone: TICKS_UNTIL_PHEROMONE,
-            timings: Timings {
-                ant_updates: Default::default(),
-                keep_ants: Default::default(),
-                neural_network_updates: Default::default(),
-                pheromone_updates: Default::default(),
-                pheromone_spawn: Default::default(),
-                pheromone_remove: Default::default(),
-                pick_up_food: Default::default(),
-                drop_of_food: Default::default(),
-                see_food: Default::default(),
-            },
-            stats: Stats {
-                step_count: 0,
-                picked_up_food: 0,
-                dropped_of_food: 0,
-            },
-            neural_network,
+            // only paid for by the callers looking at the timings
+            profiler: Profiler::disabled(),
+            stats,
+            events: Events::default(),
+            validate: false,
+            scenario,
+            castes,
         }
     }
-    pub fn timings(&self) -> &Timings {
-        &self.timings
+    pub fn profiler(&self) -> &Profiler {
+        &self.profiler
+    }
+
+    pub fn profiler_mut(&mut self) -> &mut Profiler {
+        &mut self.profiler
     }
 
     pub fn ants(&self) -> &Ants {
         &self.ants
     }
 
+    pub fn spawn_ant(&mut self, pos: Vec2, dir: f32, caste: usize) -> AntId {
+        assert!(caste < self.castes.len(), "Caste {} does not exist", caste);
+
+        let id = self.ants.spawn(pos, dir, caste);
+        self.stats.add_ant(id, pos);
+
+        id
+    }
+
+    /// the food carried by the ant is lost, returns false if the ant does not exist (anymore)
+    pub fn despawn_ant(&mut self, id: AntId) -> bool {
+        self.ants.despawn(id)
+    }
+
     pub fn pheromones(&self) -> &Pheromones {
         &self.pheromones
     }
-    pub fn neural_network(&self) -> &NeuralNetwork {
-        &self.neural_network
+    pub fn scenario(&self) -> &Scenario {
+        &self.scenario
+    }
+
+    pub fn scent(&self) -> &Scent {
+        &self.scent
+    }
+
+    pub fn castes(&self) -> &[Caste] {
+        &self.castes
     }
 
-    pub fn neural_network_mut(&mut self) -> &mut NeuralNetwork {
-        &mut self.neural_network
+    pub fn castes_mut(&mut self) -> &mut [Caste] {
+        &mut self.castes
     }
 
     pub fn foods(&self) -> Vec<&Food> {
-        self.foods.all()
+        self.foods.iter().collect_vec()
     }
     pub fn stats(&self) -> &Stats {
         &self.stats
     }
 
+    /// events are only collected while enabled
+    pub fn set_events_enabled(&mut self, enabled: bool) {
+        self.events.set_enabled(enabled);
+    }
+
+    /// everything that happened since the last call
+    pub fn drain_events(&mut self) -> impl Iterator<Item = Event> + '_ {
+        self.events.drain()
+    }
+
+    /// between 0 and 1, 0 = midnight, 0.5 = noon, always noon without a day night cycle
+    pub fn time_of_day(&self) -> f32 {
+        self.scenario.day_night.as_ref().map_or(0.5, |day_night| {
+            day_night.time_of_day(self.stats.step_count)
+        })
+    }
+
+    /// 0 at midnight, 1 at noon
+    pub fn daylight(&self) -> f32 {
+        self.scenario
+            .day_night
+            .as_ref()
+            .map_or(1., |day_night| day_night.daylight(self.stats.step_count))
+    }
+
+    /// share of the caste see distance visible right now
+    pub fn see_distance_share(&self) -> f32 {
+        let daylight = self.daylight();
+
+        self.scenario.day_night.as_ref().map_or(1., |day_night| {
+            day_night.night_see_distance + (1. - day_night.night_see_distance) * daylight
+        })
+    }
+
+    /// the first terminal condition reached, `None` while the episode should go on
+    pub fn termination(&self, policy: &EpisodePolicy) -> Option<Termination> {
+        if policy.stop_when_extinct && self.ants.is_empty() {
+            return Some(Termination::ColonyExtinct);
+        }
+
+        if policy.stop_when_food_delivered
+            && self.foods.is_empty()
+            && self.ants.carried_foods.iter().all(|count| *count == 0)
+        {
+            return Some(Termination::AllFoodDelivered);
+        }
+
+        if let Some(no_progress_ticks) = policy.no_progress_ticks {
+            if self.stats.step_count - self.stats.last_progress_step >= no_progress_ticks {
+                return Some(Termination::NoProgress);
+            }
+        }
+
+        if self.stats.step_count >= policy.max_steps {
+            return Some(Termination::MaxSteps);
+        }
+
+        None
+    }
+
+    /// steps until the policy ends the episode
+    pub fn run_episode(&mut self, policy: &EpisodePolicy) -> Termination {
+        self.run_episode_with(policy, |_| {})
+    }
+
+    /// same as `run_episode`, calling `after_step` after every step
+    pub fn run_episode_with<F>(&mut self, policy: &EpisodePolicy, mut after_step: F) -> Termination
+    where
+        F: FnMut(&Simulation),
+    {
+        loop {
+            if let Some(termination) = self.termination(policy) {
+                return termination;
+            }
+
+            self.step();
+            after_step(self);
+        }
+    }
+
     pub fn step(&mut self) {
+        let step = self.profiler.start("step");
         self.stats.step_count += 1;
 
-        Simulation::update_network(&mut self.ants, &self.neural_network, &mut self.timings);
-        Simulation::update_ants(&mut self.ants, &mut self.timings);
-        Simulation::see_food(&mut self.ants, &mut self.foods, &mut self.timings);
-        Simulation::keep_ants(&mut self.ants, &mut self.timings);
+        let time_of_day = self.time_of_day();
+        let daylight = self.daylight();
+        let see_distance = self.see_distance_share();
+
+        let (evaporation, food_available) = match &self.scenario.day_night {
+            Some(day_night) => (
+                day_night.night_evaporation + (1. - day_night.night_evaporation) * daylight,
+                day_night.food_at_night || daylight >= 0.5,
+            ),
+            None => (1., true),
+        };
+
+        Simulation::update_network(
+            &mut self.ants,
+            &self.castes,
+            time_of_day,
+            &mut self.profiler,
+        );
+
+        if self.scenario.ant_collision {
+            Simulation::collide_ants(
+                &mut self.ants,
+                &mut self.ant_grid,
+                &mut self.collision_pushes,
+                &mut self.profiler,
+            );
+        }
+
+        Simulation::update_ants(
+            &mut self.ants,
+            &self.castes,
+            &self.scenario,
+            &mut self.profiler,
+        );
+        Simulation::see_food(
+            &mut self.ants,
+            &self.castes,
+            &self.foods,
+            see_distance,
+            &mut self.profiler,
+        );
+        Simulation::keep_ants(&mut self.ants, &mut self.profiler, &mut self.events);
+        Simulation::sense_pheromones(&mut self.ants, &self.pheromones, &mut self.profiler);
+
+        // - 1 so the field is ready in the first tick
+        if (self.stats.step_count - 1).is_multiple_of(SCENT_UPDATE_TICKS) {
+            Simulation::update_scent(&mut self.scent, &self.foods, &mut self.profiler);
+        }
+
+        Simulation::sense_scent(&mut self.ants, &self.scent, &mut self.profiler);
+        Simulation::track_ants(
+            &self.ants,
+            &self.castes,
+            &mut self.profiler,
+            &mut self.stats,
+        );
+
+        if let Some(wind) = &self.scenario.wind {
+            Simulation::drift_pheromones(&mut self.pheromones, wind, &mut self.profiler);
+        }
 
         if self.ticks_until_pheromone == 0 {
             self.ticks_until_pheromone = TICKS_UNTIL_PHEROMONE;
-            Simulation::spawn_pheromones(&mut self.pheromones, &self.ants, &mut self.timings);
+            Simulation::spawn_pheromones(
+                &mut self.pheromones,
+                &self.ants,
+                &mut self.profiler,
+                &mut self.events,
+            );
         } else {
             self.ticks_until_pheromone -= 1;
         }
 
         Simulation::update_pheromones(
             &mut self.pheromones,
-            self.ants.positions.len(),
-            &mut self.timings,
+            evaporation,
+            &mut self.profiler,
+            &mut self.events,
+        );
+
+        if food_available {
+            Simulation::pick_up_food(
+                &mut self.ants,
+                &self.castes,
+                &mut self.foods,
+                &mut self.near_food,
+                &mut self.profiler,
+                &mut self.stats,
+                &mut self.events,
+            );
+        }
+
+        Simulation::drop_of_food(
+            &mut self.ants,
+            &self.scenario,
+            &mut self.profiler,
+            &mut self.stats,
+            &mut self.events,
         );
-        Simulation::pick_up_food(
+        if self.scenario.food_handover {
+            Simulation::hand_over_food(
+                &mut self.ants,
+                &self.castes,
+                &mut self.ant_grid,
+                &mut self.profiler,
+                &mut self.stats,
+                &mut self.events,
+            );
+        }
+
+        Simulation::cache_food(
             &mut self.ants,
             &mut self.foods,
-            &mut self.timings,
+            &mut self.profiler,
             &mut self.stats,
+            &mut self.events,
         );
-        Simulation::drop_of_food(&mut self.ants, &mut self.timings, &mut self.stats);
-    }
 
-    fn update_network(ants: &mut Ants, neural_network: &NeuralNetwork, timings: &mut Timings) {
-        let instant = Instant::now();
+        self.profiler.end(step);
 
-        for index in 0..ants.positions.len() {
-            let pos = &ants.positions[index];
-            let dir = &ants.dirs[index];
-            let target_dir = &ants.target_dirs[index];
-            let carries_food = ants.caries_foods[index];
-            let rays = &ants.rays[index];
+        if self.validate {
+            if let Err(error) = self.check_invariants() {
+                panic!("{}", error);
+            }
+        }
+    }
 
-            let mut values = vec![
-                pos.x / GAME_SIZE,
-                pos.y / GAME_SIZE,
-                dir / (PI * 2.),
-                target_dir / (PI * 2.),
-                if carries_food { 1. } else { -1. },
-            ];
+    fn update_network(
+        ants: &mut Ants,
+        castes: &[Caste],
+        time_of_day: f32,
+        profiler: &mut Profiler,
+    ) {
+        // cyclic so midnight is next to both ends of the day
+        let (time_sin, time_cos) = (time_of_day * PI * 2.).sin_cos();
+
+        let span = profiler.start("update_network");
+
+        for_each_chunk!(
+            ants.len(),
+            |offset: usize,
+             (target_dirs, pheromone_intensities, drops_food): (
+                &mut [f32],
+                &mut [[f32; PHEROMONE_CHANNEL_COUNT]],
+                &mut [bool],
+            )| {
+                NETWORK_BUFFER.with_borrow_mut(|values| {
+                    for (i, target_dir) in target_dirs.iter_mut().enumerate() {
+                        let index = offset + i;
+
+                        let pos = &ants.positions[index];
+                        let dir = &ants.dirs[index];
+                        let caste = &castes[ants.castes[index]];
+                        let rays = &ants.rays[index];
+
+                        // -1 = empty, 1 = full
+                        let load = ants.carried_foods[index] as f32 / caste.carry_capacity as f32;
+
+                        values.clear();
+                        values.extend_from_slice(&[
+                            pos.x / GAME_SIZE,
+                            pos.y / GAME_SIZE,
+                            dir / (PI * 2.),
+                            *target_dir / (PI * 2.),
+                            load * 2. - 1.,
+                            time_sin,
+                            time_cos,
+                        ]);
+                        values.extend_from_slice(rays);
+
+                        // squash the unbounded sums into 0..1
+                        for sense in ants.pheromone_senses[index]
+                            .iter()
+                            .chain(&ants.scent_senses[index])
+                        {
+                            values.push(1. - (-sense).exp());
+                        }
 
-            for ray in rays {
-                values.push(*ray);
-            }
+                        let values = caste.neural_network.run_with_buffer(values);
 
-            let values = neural_network.run(values);
+                        *target_dir += values[0] / 120.;
 
-            ants.target_dirs[index] += values[0] / 120.;
-            ants.pheromone_colors[index] = (values[1], values[2], values[3]);
-        }
+                        for channel in PheromoneChannel::ALL {
+                            pheromone_intensities[i][channel.index()] =
+                                values[1 + channel.index()].clamp(0., 1.);
+                        }
 
-        timings.neural_network_updates.add(&instant.elapsed());
+                        drops_food[i] = values[1 + PHEROMONE_CHANNEL_COUNT] > DROP_FOOD_THRESHOLD;
+                    }
+                });
+            },
+            ants.target_dirs,
+            ants.pheromone_intensities,
+            ants.drops_food,
+        );
+
+        profiler.end(span);
     }
 
-    fn update_pheromones(pheromones: &mut Pheromones, ant_count: usize, timings: &mut Timings) {
-        let instant = Instant::now();
+    /// `evaporation` scales how fast the pheromones vanish, 1 = normal speed
+    fn update_pheromones(
+        pheromones: &mut Pheromones,
+        evaporation: f32,
+        profiler: &mut Profiler,
+        events: &mut Events,
+    ) {
+        let update = profiler.start("update_pheromones");
+        let span = profiler.start("decay");
+
+        pheromones.groups.iter_mut().flatten().for_each(|group| {
+            group.size *= 1.002;
+            group.strength *= group.channel.decay_with(evaporation);
+        });
 
-        pheromones
-            .sizes
-            .iter_mut()
-            .filter(|size| size.is_some())
-            .for_each(|size| *size.as_mut().unwrap() *= 1.002);
+        pheromones.decay_field(evaporation);
 
-        timings.pheromone_updates.add(&instant.elapsed());
+        profiler.end(span);
 
-        let instant = Instant::now();
+        let span = profiler.start("remove");
 
-        let to_be_removed = pheromones
-            .sizes
-            .iter()
-            .enumerate()
-            .filter(|(_, size)| size.is_some())
-            //todo extract density calc to function
-            .filter(|(_, size)| 5. / (size.unwrap() * size.unwrap() * PI) < 0.01)
-            .map(|(index, _)| index)
-            .next();
+        let mut removed_any = false;
 
-        if let Some(to_be_removed) = to_be_removed {
-            //todo utility function - use while spawing and rendering
-            let index_range = ant_count * to_be_removed..ant_count * (to_be_removed + 1);
+        for (index, group) in pheromones.groups.iter_mut().enumerate() {
+            if group
+                .as_ref()
+                .is_some_and(|group| group.strength < PHEROMONE_MIN_STRENGTH)
+            {
+                *group = None;
+                removed_any = true;
 
+                events.emit(Event::PheromoneExpired { group: index });
+            }
+        }
+
+        if removed_any {
+            let groups = &pheromones.groups;
             pheromones
                 .grid
-                .retain(|pheromone| !index_range.contains(pheromone));
-
-            pheromones.sizes[to_be_removed] = None;
+                .retain(|(group, _)| groups[*group].is_some());
         }
 
-        timings.pheromone_remove.add(&instant.elapsed());
+        profiler.end(span);
+        profiler.end(update);
+    }
+
+    fn update_ants(
+        ants: &mut Ants,
+        castes: &[Caste],
+        scenario: &Scenario,
+        profiler: &mut Profiler,
+    ) {
+        let span = profiler.start("update_ants");
+
+        for_each_chunk!(
+            ants.len(),
+            |offset: usize,
+             (positions, dirs, target_dirs): (&mut [Vec2], &mut [f32], &mut [f32])| {
+                for i in 0..positions.len() {
+                    let index = offset + i;
+
+                    let pos = positions[i];
+                    let mut dir = dirs[i];
+                    let target_dir = target_dirs[i];
+
+                    //rotate to target
+                    //todo dont do that... dont create vec`s...
+                    let angle_diff =
+                        Vec2::from_angle(target_dir).angle_between(Vec2::from_angle(dir));
+
+                    dir += angle_diff * 0.01;
+                    dir %= PI * 2.;
+
+                    dirs[i] = dir;
+                    target_dirs[i] = target_dir % (PI * 2.);
+
+                    //move ant
+                    //calc how fast to move based on how strong the ant is turning
+                    let mov_speed = 1. - angle_diff.abs() / (PI * 2.);
+                    let mov_speed = castes[ants.castes[index]].speed * mov_speed;
+
+                    let mov_speed =
+                        if scenario.closest_nest(pos).1 < ANT_HILL_RADIUS * ANT_HILL_RADIUS {
+                            mov_speed / (1. + ants.neighbours[index] as f32 * ANT_CROWDING_SLOWDOWN)
+                        } else {
+                            mov_speed
+                        };
+                    // 60 = frame rate
+                    let mov_speed = mov_speed / 60.;
+
+                    // 60 = frame rate
+                    let drift = scenario
+                        .wind
+                        .as_ref()
+                        .map_or(Vec2::ZERO, |wind| wind.at(pos) * wind.ant_drift / 60.);
+
+                    positions[i] = pos + Vec2::from_angle(dir) * mov_speed + drift
+                }
+            },
+            ants.positions,
+            ants.dirs,
+            ants.target_dirs,
+        );
+
+        profiler.end(span);
+    }
+
+    fn drift_pheromones(pheromones: &mut Pheromones, wind: &Wind, profiler: &mut Profiler) {
+        let span = profiler.start("drift_pheromones");
+
+        // 60 = frame rate
+        pheromones.drift(wind, 1. / 60.);
+
+        profiler.end(span);
     }
 
-    fn update_ants(ants: &mut Ants, timings: &mut Timings) {
-        let instant = Instant::now();
+    /// pushes overlapping ants apart and counts their neighbours
+    fn collide_ants(
+        ants: &mut Ants,
+        ant_grid: &mut Grid<usize>,
+        pushes: &mut Vec<Vec2>,
+        profiler: &mut Profiler,
+    ) {
+        let span = profiler.start("collide_ants");
+
+        const MIN_DISTANCE: f32 = ANT_COLLISION_RADIUS * 2.;
+        // ants on the exact same spot need some direction to get apart
+        const GOLDEN_ANGLE: f32 = 2.399_963;
+
+        ant_grid.clear();
 
-        for index in 0..ants.positions.len() {
+        for (index, pos) in ants.positions.iter().enumerate() {
+            ant_grid.insert(pos, index);
+        }
+
+        pushes.clear();
+        pushes.resize(ants.len(), Vec2::ZERO);
+
+        for (index, push) in pushes.iter_mut().enumerate() {
             let pos = ants.positions[index];
-            let mut dir = ants.dirs[index];
-            let target_dir = ants.target_dirs[index];
+            let mut neighbours = 0;
 
-            //rotate to target
-            //todo dont do that... dont create vec`s...
-            let angle_diff = Vec2::from_angle(target_dir).angle_between(Vec2::from_angle(dir));
+            ant_grid.for_each(pos, MIN_DISTANCE, |others| {
+                for &other in others.iter() {
+                    if other == index {
+                        continue;
+                    }
+
+                    let offset = pos - ants.positions[other];
+                    let distance = offset.length();
 
-            dir += angle_diff * 0.01;
-            dir %= PI * 2.;
+                    if distance >= MIN_DISTANCE {
+                        continue;
+                    }
 
-            ants.dirs[index] = dir;
-            ants.target_dirs[index] = target_dir % (PI * 2.);
+                    let away = if distance > f32::EPSILON {
+                        offset / distance
+                    } else {
+                        // opposite directions for both ants of the pair
+                        let side = if index < other { 1. } else { -1. };
+                        Vec2::from_angle(index.min(other) as f32 * GOLDEN_ANGLE) * side
+                    };
+
+                    // both ants get pushed, so each only moves half of the overlap
+                    *push += away * (MIN_DISTANCE - distance) / 2.;
+                    neighbours += 1;
+                }
+            });
 
-            //move ant
-            //calc how fast to move based on how strong the ant is turning
-            let mov_speed = 1. - angle_diff.abs() / (PI * 2.);
-            let mov_speed = crate::ants::ANT_SPEED * mov_speed;
-            // 60 = frame rate
-            let mov_speed = mov_speed / 60.;
+            ants.neighbours[index] = neighbours;
+        }
 
-            ants.positions[index] = pos + Vec2::from_angle(dir) * mov_speed
+        // ants spawned on the same spot would otherwise all add up and get thrown off the map
+        for (pos, push) in ants.positions.iter_mut().zip(pushes.iter()) {
+            *pos += push.clamp_length_max(MIN_DISTANCE);
         }
 
-        timings.ant_updates.add(&instant.elapsed());
+        profiler.end(span);
     }
 
-    fn keep_ants(ants: &mut Ants, timings: &mut Timings) {
-        let instant = Instant::now();
+    fn keep_ants(ants: &mut Ants, profiler: &mut Profiler, events: &mut Events) {
+        let span = profiler.start("keep_ants");
 
         //todo maybe not zip but didnt get faster...
-        for ((pos, dir), target_dir) in ants
+        for (((pos, dir), target_dir), id) in ants
             .positions
             .iter_mut()
             .zip(ants.dirs.iter_mut())
             .zip(ants.target_dirs.iter_mut())
+            .zip(ants.ids.iter())
         {
+            let old_dir = *dir;
+
             if pos.x > GAME_SIZE {
                 pos.x -= 10.;
                 *dir += PI;
@@ -329,167 +762,389 @@ impl Simulation {
                 *dir += PI;
                 *target_dir += PI;
             }
+
+            if *dir != old_dir {
+                *dir %= PI * 2.;
+                *target_dir %= PI * 2.;
+
+                events.emit(Event::AntBounced {
+                    ant: *id,
+                    pos: *pos,
+                });
+            }
         }
 
-        timings.keep_ants.add(&instant.elapsed());
+        profiler.end(span);
+    }
+
+    fn sense_pheromones(ants: &mut Ants, pheromones: &Pheromones, profiler: &mut Profiler) {
+        let span = profiler.start("sense_pheromones");
+
+        for_each_chunk!(
+            ants.len(),
+            |offset: usize, (senses,): (&mut [[f32; PHEROMONE_CHANNEL_COUNT * 2]],)| {
+                for (i, senses) in senses.iter_mut().enumerate() {
+                    let index = offset + i;
+                    let antennas =
+                        Ants::get_antenna_positions(ants.positions[index], ants.dirs[index]);
+
+                    for (side, antenna) in antennas.into_iter().enumerate() {
+                        let strengths = pheromones.sense(antenna);
+                        let offset = side * PHEROMONE_CHANNEL_COUNT;
+
+                        senses[offset..offset + PHEROMONE_CHANNEL_COUNT]
+                            .copy_from_slice(&strengths);
+                    }
+                }
+            },
+            ants.pheromone_senses,
+        );
+
+        profiler.end(span);
     }
 
-    fn spawn_pheromones(pheromones: &mut Pheromones, ants: &Ants, timings: &mut Timings) {
-        let instant = Instant::now();
+    fn update_scent(scent: &mut Scent, foods: &Grid<Food>, profiler: &mut Profiler) {
+        let span = profiler.start("update_scent");
 
-        //todo dont dynamically build up - precompute needed size and use that...
-        // no need to handle sizes as optional than simple count on with index and overwrite than
+        scent.update(foods);
+
+        profiler.end(span);
+    }
 
-        let to_be_replaced = pheromones
-            .sizes
-            .iter()
-            .find_position(|value| value.is_none());
+    fn sense_scent(ants: &mut Ants, scent: &Scent, profiler: &mut Profiler) {
+        let span = profiler.start("sense_scent");
 
-        if to_be_replaced.is_some() {
-            let (to_be_replaced, _) = to_be_replaced.unwrap();
+        for_each_chunk!(
+            ants.len(),
+            |offset: usize, (senses,): (&mut [[f32; 2]],)| {
+                for (i, senses) in senses.iter_mut().enumerate() {
+                    let index = offset + i;
+                    let antennas =
+                        Ants::get_antenna_positions(ants.positions[index], ants.dirs[index]);
 
-            pheromones.sizes[to_be_replaced] = Some(1.);
+                    *senses = antennas.map(|antenna| scent.sense(antenna));
+                }
+            },
+            ants.scent_senses,
+        );
 
-            let offset = ants.positions.len() * to_be_replaced;
+        profiler.end(span);
+    }
 
-            for index in 0..ants.positions.len() {
-                pheromones.grid.insert(&ants.positions[index], index);
-                pheromones.positions[index + offset] = ants.positions[index];
-                pheromones.colors[index + offset] = ants.pheromone_colors[index];
-            }
-        } else {
-            pheromones.sizes.push(Some(1.));
+    fn track_ants(ants: &Ants, castes: &[Caste], profiler: &mut Profiler, stats: &mut Stats) {
+        let span = profiler.start("track_ants");
+
+        for ant in ants.iter() {
+            let speed = castes[ant.caste()].speed;
+            stats.track_ant(
+                ant.id(),
+                ant.pos(),
+                speed,
+                ant.carries_food(),
+                ant.neighbours() > 0,
+            );
+        }
 
-            let len = ants.positions.len();
+        profiler.end(span);
+    }
 
-            for index in 0..len {
-                pheromones.grid.insert(&ants.positions[index], len + index);
-                pheromones.positions.push(ants.positions[index]);
-                pheromones.colors.push(ants.pheromone_colors[index]);
+    fn spawn_pheromones(
+        pheromones: &mut Pheromones,
+        ants: &Ants,
+        profiler: &mut Profiler,
+        events: &mut Events,
+    ) {
+        let span = profiler.start("spawn_pheromones");
+
+        for channel in PheromoneChannel::ALL {
+            let deposits = |index: &usize| ants.pheromone_intensities[*index][channel.index()] > 0.;
+            let count = (0..ants.len()).filter(deposits).count();
+
+            if count == 0 {
+                continue;
             }
+
+            let group_index = pheromones
+                .groups
+                .iter()
+                .position(|group| group.is_none())
+                .unwrap_or_else(|| {
+                    pheromones.groups.push(None);
+                    pheromones.groups.len() - 1
+                });
+
+            let mut group = PheromoneGroup {
+                channel,
+                strength: 1.,
+                size: 1.,
+                positions: Vec::with_capacity(count),
+                intensities: Vec::with_capacity(count),
+            };
+
+            for index in (0..ants.len()).filter(deposits) {
+                let pos = ants.positions[index];
+
+                pheromones
+                    .grid
+                    .insert(&pos, (group_index, group.positions.len()));
+                let intensity = ants.pheromone_intensities[index][channel.index()];
+
+                pheromones.deposit(pos, channel, intensity);
+                group.positions.push(pos);
+                group.intensities.push(intensity);
+
+                events.emit(Event::PheromoneSpawned {
+                    ant: ants.ids[index],
+                    group: group_index,
+                    channel,
+                    pos,
+                });
+            }
+
+            pheromones.groups[group_index] = Some(group);
         }
 
-        timings.pheromone_spawn.add(&instant.elapsed());
+        profiler.end(span);
     }
 
     fn pick_up_food(
         ants: &mut Ants,
+        castes: &[Caste],
         foods: &mut Grid<Food>,
-        timings: &mut Timings,
+        near_food: &mut Vec<bool>,
+        profiler: &mut Profiler,
         stats: &mut Stats,
+        events: &mut Events,
     ) {
-        let instant = Instant::now();
+        let span = profiler.start("pick_up_food");
+
+        near_food.clear();
+        near_food.resize(ants.len(), false);
+
+        // most ants have no food in reach, finding the few that do can run in parallel
+        for_each_chunk!(
+            ants.len(),
+            |offset: usize, (near_food,): (&mut [bool],)| {
+                for (i, near_food) in near_food.iter_mut().enumerate() {
+                    let index = offset + i;
+
+                    // ants still dropping would pick their own cache right back up
+                    *near_food = !ants.drops_food[index]
+                        && ants.carried_foods[index] < castes[ants.castes[index]].carry_capacity
+                        && foods
+                            .within(ants.positions[index], ANT_PICK_UP_DISTANCE)
+                            .next()
+                            .is_some();
+                }
+            },
+            near_food,
+        );
 
-        for (index, carries) in ants
-            .caries_foods
+        // in index order, so the same ant gets the food no matter the thread count
+        for (index, carried) in ants
+            .carried_foods
             .iter_mut()
             .enumerate()
-            .filter(|(_, carries)| !**carries)
+            .filter(|(index, _)| near_food[*index])
         {
+            let id = ants.ids[index];
             let pos = ants.positions[index];
 
-            foods.for_each(pos, ANT_PICK_UP_DISTANCE, |foods| {
-                let mut picked_up_food = None;
+            if let Some(food) = foods.remove_within(pos, ANT_PICK_UP_DISTANCE, |_| true) {
+                stats.pick_up(id, pos, food.is_cached());
+                events.emit(Event::FoodPickedUp { ant: id, pos });
+                *carried += 1;
+            }
+        }
+
+        profiler.end(span);
+    }
+
+    /// `see_distance` is the share of the caste see distance visible right now
+    fn see_food(
+        ants: &mut Ants,
+        castes: &[Caste],
+        foods: &Grid<Food>,
+        see_distance: f32,
+        profiler: &mut Profiler,
+    ) {
+        let span = profiler.start("see_food");
+
+        for_each_chunk!(
+            ants.len(),
+            |offset: usize, (rays,): (&mut [[f32; ANT_RAY_COUNT]],)| {
+                for (i, rays) in rays.iter_mut().enumerate() {
+                    let index = offset + i;
+
+                    let pos = ants.positions[index];
+                    let dir = ants.dirs[index];
+                    let see_distance = castes[ants.castes[index]].see_distance * see_distance;
 
-                for (index, food) in foods.iter().enumerate() {
-                    let distance =
-                        vec2(food.pos().x - pos.x, food.pos().y - pos.y).length_squared();
-                    if distance < ANT_PICK_UP_DISTANCE * ANT_PICK_UP_DISTANCE {
-                        stats.picked_up_food += 1;
-                        picked_up_food = Some(index);
-                        break;
+                    // most ants don't have any food around
+                    if foods.within(pos, see_distance).next().is_none() {
+                        rays.fill(-1.);
+                        continue;
                     }
-                }
 
-                if let Some(index) = picked_up_food {
-                    *carries = true;
-                    foods.remove(index);
+                    for (ray, direction) in rays.iter_mut().zip(Ants::ray_directions(dir)) {
+                        let nearest =
+                            foods.cast_ray(pos, direction, see_distance, FOOD_SIZE, |food| {
+                                if food.pos().distance_squared(pos) > see_distance * see_distance {
+                                    return None;
+                                }
+
+                                ray_inserect_circle(*food.pos(), FOOD_SIZE, pos, direction)
+                            });
+
+                        *ray = nearest.map_or(-1., |(distance, _)| distance);
+                    }
                 }
-            });
-        }
+            },
+            ants.rays,
+        );
 
-        timings.pick_up_food.add(&instant.elapsed());
+        profiler.end(span);
     }
 
-    fn see_food(ants: &mut Ants, foods: &mut Grid<Food>, timings: &mut Timings) {
-        let instant = Instant::now();
+    fn drop_of_food(
+        ants: &mut Ants,
+        scenario: &Scenario,
+        profiler: &mut Profiler,
+        stats: &mut Stats,
+        events: &mut Events,
+    ) {
+        let span = profiler.start("drop_of_food");
 
-        for index in 0..ants.positions.len() {
-            let rays = &mut ants.rays[index];
+        for (index, carried) in ants
+            .carried_foods
+            .iter_mut()
+            .enumerate()
+            .filter(|(_, carried)| **carried > 0)
+        {
             let pos = ants.positions[index];
-            let dir = ants.dirs[index];
+            let (nest, distance_squared) = scenario.closest_nest(pos);
 
-            let ray_directions = OnceCell::new();
-            let mut nearest_foods = OnceCell::new();
+            if distance_squared > ANT_HILL_RADIUS * ANT_HILL_RADIUS {
+                continue;
+            }
 
-            foods.for_each(pos, ANT_SEE_DISTANCE, |foods| {
-                let ray_directions =
-                    ray_directions.get_or_init(|| Ants::get_ray_directions(dir).collect_vec());
+            stats.deliver(ants.ids[index], nest, pos, *carried);
+            events.emit(Event::FoodDelivered {
+                ant: ants.ids[index],
+                nest,
+                pos,
+                count: *carried,
+            });
+            *carried = 0
+        }
 
-                // TODO - https://github.com/rust-lang/rust/issues/121641
-                nearest_foods.get_or_init(|| vec![None; rays.len()]);
-                let nearest_foods = nearest_foods.get_mut().unwrap();
+        profiler.end(span);
+    }
 
-                for food in &mut *foods {
-                    let distance = food.pos().distance_squared(pos);
-                    if distance > ANT_SEE_DISTANCE * ANT_SEE_DISTANCE {
-                        continue;
-                    }
+    /// ants dropping food give as much as fits to a touching ant which doesn't drop food
+    fn hand_over_food(
+        ants: &mut Ants,
+        castes: &[Caste],
+        ant_grid: &mut Grid<usize>,
+        profiler: &mut Profiler,
+        stats: &mut Stats,
+        events: &mut Events,
+    ) {
+        let span = profiler.start("hand_over_food");
 
-                    for (index, ray_direction) in ray_directions.iter().enumerate() {
-                        if let Some(nearest) = nearest_foods[index] {
-                            if nearest < distance {
-                                continue;
-                            }
-                        }
+        let any_giver =
+            (0..ants.len()).any(|index| ants.drops_food[index] && ants.carried_foods[index] > 0);
 
-                        let intersection =
-                            ray_inserect_circle(*food.pos(), FOOD_SIZE, pos, *ray_direction);
+        if !any_giver {
+            profiler.end(span);
+            return;
+        }
 
-                        if let Some(intersection) = intersection {
-                            if let Some(nearest) = nearest_foods[index] {
-                                if intersection < nearest {
-                                    nearest_foods[index] = Some(intersection)
-                                }
-                            } else {
-                                nearest_foods[index] = Some(intersection)
-                            }
-                        }
+        // the ants moved since the collision filled the grid
+        ant_grid.clear();
+
+        for (index, pos) in ants.positions.iter().enumerate() {
+            ant_grid.insert(pos, index);
+        }
+
+        for index in 0..ants.len() {
+            if !ants.drops_food[index] || ants.carried_foods[index] == 0 {
+                continue;
+            }
+
+            let pos = ants.positions[index];
+            // the lowest index so the receiver does not depend on the order in the grid
+            let mut receiver: Option<usize> = None;
+
+            ant_grid.for_each(pos, ANT_PICK_UP_DISTANCE, |others| {
+                for &other in others.iter() {
+                    if other != index
+                        && receiver.is_none_or(|receiver| other < receiver)
+                        && !ants.drops_food[other]
+                        && ants.carried_foods[other] < castes[ants.castes[other]].carry_capacity
+                        && ants.positions[other].distance_squared(pos)
+                            < ANT_PICK_UP_DISTANCE * ANT_PICK_UP_DISTANCE
+                    {
+                        receiver = Some(other);
                     }
                 }
             });
 
-            if let Some(nearest_foods) = nearest_foods.get() {
-                nearest_foods
-                    .iter()
-                    .map(|nearest_food| nearest_food.unwrap_or(-1.0))
-                    .enumerate()
-                    .for_each(|(index, value)| rays[index] = value);
-            } else {
-                rays.fill(-1.);
-            }
+            let Some(receiver) = receiver else {
+                continue;
+            };
+
+            let free = castes[ants.castes[receiver]].carry_capacity - ants.carried_foods[receiver];
+            let count = free.min(ants.carried_foods[index]);
+
+            ants.carried_foods[receiver] += count;
+            ants.carried_foods[index] -= count;
+
+            stats.hand_over(ants.ids[receiver], pos, count);
+            events.emit(Event::FoodHandedOver {
+                from: ants.ids[index],
+                to: ants.ids[receiver],
+                pos,
+                count,
+            });
         }
 
-        timings.see_food.add(&instant.elapsed());
+        profiler.end(span);
     }
 
-    fn drop_of_food(ants: &mut Ants, timings: &mut Timings, stats: &mut Stats) {
-        let instant = Instant::now();
+    /// ants dropping food outside of the nest
+    fn cache_food(
+        ants: &mut Ants,
+        foods: &mut Grid<Food>,
+        profiler: &mut Profiler,
+        stats: &mut Stats,
+        events: &mut Events,
+    ) {
+        let span = profiler.start("cache_food");
 
-        for (index, caries) in ants
-            .caries_foods
-            .iter_mut()
-            .enumerate()
-            .filter(|(_, carries)| **carries)
-        {
-            if ants.positions[index].length_squared() > ANT_HILL_RADIUS * ANT_HILL_RADIUS {
+        for index in 0..ants.len() {
+            let count = ants.carried_foods[index];
+
+            if !ants.drops_food[index] || count == 0 {
                 continue;
             }
 
-            stats.dropped_of_food += 1;
-            *caries = false
+            let pos = ants.positions[index];
+
+            for _ in 0..count {
+                foods.insert(&pos, Food::cached(pos));
+            }
+
+            ants.carried_foods[index] = 0;
+
+            stats.cached_food += count;
+            events.emit(Event::FoodDropped {
+                ant: ants.ids[index],
+                pos,
+                count,
+            });
         }
 
-        timings.drop_of_food.add(&instant.elapsed());
+        profiler.end(span);
     }
 }
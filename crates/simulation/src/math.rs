@@ -1,5 +1,17 @@
 use glam::Vec2;
 
+/// splitmix64, always the same number between 0 and 1 for the same seed and index
+pub fn hash_unit(seed: u64, index: u64) -> f32 {
+    let mut z = seed
+        .wrapping_add(index.wrapping_mul(0x9E3779B97F4A7C15))
+        .wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^= z >> 31;
+
+    (z >> 40) as f32 / (1u64 << 24) as f32
+}
+
 /// math based on https://www.youtube.com/watch?v=23kTf-36Fcw
 pub fn ray_inserect_circle(
     circle_center: Vec2,
@@ -35,6 +47,29 @@ pub fn ray_inserect_circle(
     Some(result)
 }
 
+/// the four cells around `cell` of a `size` x `size` field with their weights
+///
+/// `cell` is measured in cells relative to the cell centers
+pub fn bilinear_cells(cell: Vec2, size: usize) -> [(usize, f32); 4] {
+    let max_cell = size - 1;
+    let cell = cell.clamp(Vec2::ZERO, Vec2::splat(max_cell as f32));
+
+    let x0 = cell.x.floor() as usize;
+    let y0 = cell.y.floor() as usize;
+    let x1 = (x0 + 1).min(max_cell);
+    let y1 = (y0 + 1).min(max_cell);
+
+    let tx = cell.x - x0 as f32;
+    let ty = cell.y - y0 as f32;
+
+    [
+        (y0 * size + x0, (1. - tx) * (1. - ty)),
+        (y0 * size + x1, tx * (1. - ty)),
+        (y1 * size + x0, (1. - tx) * ty),
+        (y1 * size + x1, tx * ty),
+    ]
+}
+
 pub fn circle_intersects_rect(
     circle_center: Vec2,
     circle_radius: f32,
@@ -0,0 +1,153 @@
+use glam::Vec2;
+
+use crate::pheromones::PheromoneChannel;
+use crate::scenario::Wind;
+use crate::GAME_SIZE;
+
+/// red, green, blue and alpha between 0 and 1
+pub type Rgba = [f32; 4];
+
+pub const MAP: Rgba = [0.6, 0.4, 0.1, 1.];
+pub const NEST: Rgba = [0.8, 0.7, 0.1, 1.];
+pub const WIND: Rgba = [1., 1., 1., 0.5];
+
+/// half the length and half the width of an ant
+pub const ANT_RADII: Vec2 = Vec2::new(10., 5.);
+pub const RAY_WIDTH: f32 = 5.;
+pub const WIND_WIDTH: f32 = 2.;
+/// pheromones grow with the size of their group up to this radius
+pub const MAX_PHEROMONE_RADIUS: f32 = 10.;
+
+const WIND_ARROW_SPACING: f32 = 100.;
+/// a second of wind, short enough to not overlap the next arrow
+const MAX_WIND_ARROW_LENGTH: f32 = 40.;
+const WIND_ARROW_HEAD: f32 = 8.;
+
+/// darkest at midnight while keeping the map visible
+pub fn brightness(daylight: f32) -> f32 {
+    0.3 + 0.7 * daylight
+}
+
+pub fn map_color(daylight: f32) -> Rgba {
+    scaled(MAP, brightness(daylight))
+}
+
+pub fn ant_color(carries_food: bool) -> Rgba {
+    if carries_food {
+        [0., 1., 0., 1.]
+    } else {
+        [0., 0., 0., 1.]
+    }
+}
+
+/// caches are darker so they stand out from the food sources
+pub fn food_color(cached: bool) -> Rgba {
+    if cached {
+        [0., 0.5, 0., 1.]
+    } else {
+        [0., 1., 0., 1.]
+    }
+}
+
+pub fn pheromone_color(channel: PheromoneChannel, alpha: f32) -> Rgba {
+    match channel {
+        PheromoneChannel::ToFood => [0., 1., 0., alpha],
+        PheromoneChannel::ToHome => [0., 0., 1., alpha],
+        PheromoneChannel::Alarm => [1., 0., 0., alpha],
+    }
+}
+
+/// `ray` as sensed by the ant, -1 if it doesn't see any food
+pub fn ray_color(ray: f32) -> Rgba {
+    if ray == -1. {
+        [1., 1., 0., 1.]
+    } else {
+        [0., 1., 0., 1.]
+    }
+}
+
+/// multiplies the color channels, keeps the alpha
+pub fn scaled(color: Rgba, factor: f32) -> Rgba {
+    [
+        color[0] * factor,
+        color[1] * factor,
+        color[2] * factor,
+        color[3],
+    ]
+}
+
+/// a line from `from` to `tip` with two short lines forming the head at `tip`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindArrow {
+    pub from: Vec2,
+    pub tip: Vec2,
+    /// starts of the head lines, both end at `tip`
+    pub head: [Vec2; 2],
+}
+
+impl WindArrow {
+    /// the shaft and both head lines
+    pub fn lines(&self) -> [(Vec2, Vec2); 3] {
+        [
+            (self.from, self.tip),
+            (self.head[0], self.tip),
+            (self.head[1], self.tip),
+        ]
+    }
+}
+
+/// evenly spaced over the map, calm spots get no arrow
+pub fn wind_arrows(wind: &Wind) -> impl Iterator<Item = WindArrow> + '_ {
+    let arrows_per_side = (GAME_SIZE * 2. / WIND_ARROW_SPACING) as usize;
+
+    (0..arrows_per_side)
+        .flat_map(move |x| (0..arrows_per_side).map(move |y| (x, y)))
+        .filter_map(move |(x, y)| {
+            let from = Vec2::new(x as f32 + 0.5, y as f32 + 0.5) * WIND_ARROW_SPACING - GAME_SIZE;
+            let arrow = wind.at(from).clamp_length_max(MAX_WIND_ARROW_LENGTH);
+
+            if arrow.length_squared() < 1. {
+                return None;
+            }
+
+            let tip = from + arrow;
+            let head = arrow.normalize() * WIND_ARROW_HEAD;
+
+            Some(WindArrow {
+                from,
+                tip,
+                head: [
+                    tip - head.rotate(Vec2::new(1., 0.5)),
+                    tip - head.rotate(Vec2::new(1., -0.5)),
+                ],
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec2;
+
+    use crate::palette::{wind_arrows, MAX_WIND_ARROW_LENGTH};
+    use crate::scenario::Wind;
+
+    #[test]
+    fn wind_arrows_stay_short() {
+        let storm = Wind {
+            base: vec2(500., 0.),
+            ..Default::default()
+        };
+        let arrows = wind_arrows(&storm).collect::<Vec<_>>();
+
+        assert!(!arrows.is_empty());
+        assert!(arrows
+            .iter()
+            .all(|arrow| arrow.from.distance(arrow.tip) <= MAX_WIND_ARROW_LENGTH + 1e-3));
+
+        let calm = Wind {
+            base: vec2(0.5, 0.),
+            ..Default::default()
+        };
+        assert_eq!(wind_arrows(&calm).count(), 0);
+    }
+}
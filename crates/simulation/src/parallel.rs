@@ -0,0 +1,156 @@
+/// below this the overhead of waking up other threads is bigger than the work
+pub const PARALLEL_MIN_ANTS: usize = 4096;
+/// ants per parallel job
+pub const PARALLEL_CHUNK_SIZE: usize = 1024;
+
+/// runs `$kernel(offset, (columns...))` over chunks of the given columns
+///
+/// the columns have to be of the same length, `offset` is the index of the first ant of the chunk.
+/// small simulations and single threaded pools run the kernel once with the whole columns on the calling thread
+macro_rules! for_each_chunk {
+    ($len:expr, $kernel:expr, $($column:expr),+ $(,)?) => {{
+        let kernel = $kernel;
+
+        if $len < $crate::parallel::PARALLEL_MIN_ANTS || rayon::current_num_threads() == 1 {
+            kernel(0, ($(&mut $column[..],)+));
+        } else {
+            use rayon::prelude::*;
+
+            ($($column.par_chunks_mut($crate::parallel::PARALLEL_CHUNK_SIZE),)+)
+                .into_par_iter()
+                .enumerate()
+                .for_each(|(chunk, columns)| {
+                    kernel(chunk * $crate::parallel::PARALLEL_CHUNK_SIZE, columns)
+                });
+        }
+    }};
+}
+
+pub(crate) use for_each_chunk;
+
+#[cfg(test)]
+mod tests {
+    use neural_network::NeuralNetwork;
+
+    use crate::castes::Caste;
+    use crate::parallel::PARALLEL_MIN_ANTS;
+    use crate::scenario::Scenario;
+    use crate::{Simulation, NEURAL_NETWORK_INPUT_SIZE, NEURAL_NETWORK_OUTPUT_SIZE};
+
+    #[test]
+    fn chunks_cover_every_index() {
+        for len in [10, PARALLEL_MIN_ANTS * 3 + 7] {
+            let mut indices = vec![0; len];
+            let mut doubled = vec![0; len];
+
+            for_each_chunk!(
+                len,
+                |offset: usize, (indices, doubled): (&mut [usize], &mut [usize])| {
+                    for i in 0..indices.len() {
+                        indices[i] = offset + i;
+                        doubled[i] = (offset + i) * 2;
+                    }
+                },
+                indices,
+                doubled,
+            );
+
+            assert!(indices.iter().enumerate().all(|(i, index)| i == *index));
+            assert!(doubled.iter().enumerate().all(|(i, value)| i * 2 == *value));
+        }
+    }
+
+    #[test]
+    fn threads_do_not_change_results() {
+        let mut network = NeuralNetwork::new(NEURAL_NETWORK_INPUT_SIZE, NEURAL_NETWORK_OUTPUT_SIZE);
+        for _ in 0..10 {
+            network.mutate_strucutre();
+        }
+
+        let run = |threads: usize| {
+            let scenario = Scenario {
+                ant_count: PARALLEL_MIN_ANTS + 1000,
+                ant_collision: true,
+                food_handover: true,
+                seed: 7,
+                ..Default::default()
+            };
+            let mut simulation =
+                Simulation::with_scenario(scenario, vec![Caste::new("ant", network.clone())]);
+
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+                .install(|| {
+                    for _ in 0..10 {
+                        simulation.step();
+                    }
+                });
+
+            simulation
+        };
+
+        // one thread takes the serial path
+        let serial = run(1);
+        let parallel = run(4);
+
+        assert_eq!(serial.ants().ids(), parallel.ants().ids());
+        assert_eq!(serial.ants().positions(), parallel.ants().positions());
+        assert_eq!(serial.ants().dirs(), parallel.ants().dirs());
+        assert_eq!(
+            serial.ants().carried_foods(),
+            parallel.ants().carried_foods()
+        );
+        assert_eq!(
+            serde_json::to_string(serial.stats()).unwrap(),
+            serde_json::to_string(parallel.stats()).unwrap()
+        );
+    }
+
+    /// deterministic network so the trajectories can be pinned
+    fn seeded_network(seed: u64) -> NeuralNetwork {
+        let mut state = seed;
+        NeuralNetwork::with_weight(
+            NEURAL_NETWORK_INPUT_SIZE,
+            NEURAL_NETWORK_OUTPUT_SIZE,
+            || {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                ((state >> 40) as f32 / (1u64 << 24) as f32) * 2. - 1.
+            },
+        )
+    }
+
+    fn position_checksum(simulation: &Simulation) -> u64 {
+        simulation.ants().positions().iter().fold(0, |hash, pos| {
+            (hash ^ pos.x.to_bits() as u64 ^ ((pos.y.to_bits() as u64) << 32))
+                .wrapping_mul(0x100000001b3)
+        })
+    }
+
+    #[test]
+    fn chunked_steps_match_the_old_steps() {
+        // taken before the steps were split into chunked columns, the second run already sees food
+        for (seed, steps, checksum) in
+            [(2, 1000, 0xe0e2e5d6aae63c04), (11, 242, 0x2226f6e0aa523c03)]
+        {
+            let mut simulation =
+                Simulation::with_castes(vec![Caste::new("ant", seeded_network(seed))]);
+            for _ in 0..steps {
+                simulation.step();
+            }
+
+            assert_eq!(simulation.stats().step_count, steps);
+            assert_eq!(simulation.stats().picked_up_food, 0);
+            assert_eq!(simulation.stats().dropped_of_food, 0);
+            assert_eq!(
+                position_checksum(&simulation),
+                checksum,
+                "Seed {} moved differently",
+                seed
+            );
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use glam::Vec2;
+
+use crate::grid::Grid;
+use crate::math::bilinear_cells;
+use crate::scenario::Wind;
+
+pub const PHEROMONE_CHANNEL_COUNT: usize = 3;
+/// groups weaker than this get removed
+pub const PHEROMONE_MIN_STRENGTH: f32 = 0.01;
+/// cells per side of the field used for sensing
+pub const PHEROMONE_FIELD_SIZE: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PheromoneChannel {
+    ToFood,
+    ToHome,
+    Alarm,
+}
+
+impl PheromoneChannel {
+    pub const ALL: [PheromoneChannel; PHEROMONE_CHANNEL_COUNT] = [
+        PheromoneChannel::ToFood,
+        PheromoneChannel::ToHome,
+        PheromoneChannel::Alarm,
+    ];
+
+    pub fn index(&self) -> usize {
+        match self {
+            PheromoneChannel::ToFood => 0,
+            PheromoneChannel::ToHome => 1,
+            PheromoneChannel::Alarm => 2,
+        }
+    }
+
+    /// strength left after one tick with the evaporation sped up or slowed down by `evaporation`
+    pub fn decay_with(&self, evaporation: f32) -> f32 {
+        1. - (1. - self.decay()) * evaporation
+    }
+
+    /// strength left after one tick
+    pub fn decay(&self) -> f32 {
+        match self {
+            PheromoneChannel::ToFood => 0.996,
+            PheromoneChannel::ToHome => 0.998,
+            // alarms should only be relevant for a short time
+            PheromoneChannel::Alarm => 0.98,
+        }
+    }
+}
+
+pub struct Pheromones {
+    //todo dont all pub
+    /// (group index, index inside the group)
+    pub grid: Grid<(usize, usize)>,
+
+    // None marking a deleted group
+    pub groups: Vec<Option<PheromoneGroup>>,
+
+    // summed up strength per cell and channel, decays at the same rate as the groups
+    // sensing every single pheromone gets way to slow once the trails get dense
+    field: Vec<[f32; PHEROMONE_CHANNEL_COUNT]>,
+    // reused when the field drifts
+    drifted_field: Vec<[f32; PHEROMONE_CHANNEL_COUNT]>,
+    half_width: f32,
+}
+
+/// all pheromones of one channel spawned in the same tick
+pub struct PheromoneGroup {
+    pub channel: PheromoneChannel,
+    /// decays each tick, shared by all pheromones of the group
+    pub strength: f32,
+    pub size: f32,
+    pub positions: Vec<Vec2>,
+    /// how strongly each pheromone was deposited, between 0 and 1
+    pub intensities: Vec<f32>,
+}
+
+impl Pheromones {
+    pub fn new(half_width: f32) -> Pheromones {
+        Pheromones {
+            grid: Grid::new(25, half_width),
+            groups: vec![],
+            field: vec![[0.; PHEROMONE_CHANNEL_COUNT]; PHEROMONE_FIELD_SIZE * PHEROMONE_FIELD_SIZE],
+            drifted_field: vec![
+                [0.; PHEROMONE_CHANNEL_COUNT];
+                PHEROMONE_FIELD_SIZE * PHEROMONE_FIELD_SIZE
+            ],
+            half_width,
+        }
+    }
+
+    pub(crate) fn deposit(&mut self, pos: Vec2, channel: PheromoneChannel, intensity: f32) {
+        let cell = (pos + self.half_width) / self.field_cell_width();
+        let x = (cell.x.floor() as usize).min(PHEROMONE_FIELD_SIZE - 1);
+        let y = (cell.y.floor() as usize).min(PHEROMONE_FIELD_SIZE - 1);
+
+        self.field[y * PHEROMONE_FIELD_SIZE + x][channel.index()] += intensity;
+    }
+
+    pub(crate) fn decay_field(&mut self, evaporation: f32) {
+        let decays = PheromoneChannel::ALL.map(|channel| channel.decay_with(evaporation));
+
+        for cell in &mut self.field {
+            for (value, decay) in cell.iter_mut().zip(decays) {
+                *value *= decay;
+            }
+        }
+    }
+
+    /// moves every pheromone and the field along the wind
+    pub(crate) fn drift(&mut self, wind: &Wind, seconds: f32) {
+        for group in self.groups.iter_mut().flatten() {
+            for pos in &mut group.positions {
+                *pos += wind.at(*pos) * seconds;
+            }
+        }
+
+        self.grid.clear();
+        for (group_index, group) in self.groups.iter().enumerate() {
+            let Some(group) = group else {
+                continue;
+            };
+
+            for (index, pos) in group.positions.iter().enumerate() {
+                self.grid.insert(pos, (group_index, index));
+            }
+        }
+
+        // every cell takes the value from where the wind carried it from
+        let cell_width = self.field_cell_width();
+
+        for y in 0..PHEROMONE_FIELD_SIZE {
+            for x in 0..PHEROMONE_FIELD_SIZE {
+                let center =
+                    Vec2::new(x as f32 + 0.5, y as f32 + 0.5) * cell_width - self.half_width;
+                let source = center - wind.at(center) * seconds;
+
+                self.drifted_field[y * PHEROMONE_FIELD_SIZE + x] = self.sense(source);
+            }
+        }
+
+        std::mem::swap(&mut self.field, &mut self.drifted_field);
+    }
+
+    /// strength per channel at `pos`, interpolated between the surrounding field cells
+    pub fn sense(&self, pos: Vec2) -> [f32; PHEROMONE_CHANNEL_COUNT] {
+        // relative to the cell centers
+        let cell = (pos + self.half_width) / self.field_cell_width() - 0.5;
+
+        let mut strengths = [0.; PHEROMONE_CHANNEL_COUNT];
+
+        for (index, weight) in bilinear_cells(cell, PHEROMONE_FIELD_SIZE) {
+            for (strength, value) in strengths.iter_mut().zip(self.field[index]) {
+                *strength += value * weight;
+            }
+        }
+
+        strengths
+    }
+
+    fn field_cell_width(&self) -> f32 {
+        self.half_width * 2. / PHEROMONE_FIELD_SIZE as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec2;
+
+    use crate::pheromones::{PheromoneChannel, Pheromones};
+    use crate::scenario::Wind;
+
+    #[test]
+    fn senses_per_channel() {
+        let mut pheromones = Pheromones::new(500.);
+
+        pheromones.deposit(vec2(10., 0.), PheromoneChannel::ToHome, 0.5);
+
+        let near = pheromones.sense(vec2(12., 0.));
+        let far = pheromones.sense(vec2(-15., 0.));
+
+        assert_eq!(near[PheromoneChannel::ToFood.index()], 0.);
+        assert!(near[PheromoneChannel::ToHome.index()] > far[PheromoneChannel::ToHome.index()]);
+
+        pheromones.decay_field(1.);
+
+        let decayed = pheromones.sense(vec2(12., 0.));
+        assert!(decayed[PheromoneChannel::ToHome.index()] < near[PheromoneChannel::ToHome.index()]);
+    }
+
+    #[test]
+    fn drifts_with_wind() {
+        let mut pheromones = Pheromones::new(500.);
+        pheromones.deposit(vec2(0., 0.), PheromoneChannel::ToFood, 1.);
+
+        let wind = Wind {
+            base: vec2(10., 0.),
+            ..Default::default()
+        };
+
+        // one cell width
+        for _ in 0..10 {
+            pheromones.drift(&wind, 0.1);
+        }
+
+        let upwind = pheromones.sense(vec2(-5., 0.));
+        let downwind = pheromones.sense(vec2(15., 0.));
+
+        assert!(
+            downwind[PheromoneChannel::ToFood.index()] > upwind[PheromoneChannel::ToFood.index()]
+        );
+    }
+}
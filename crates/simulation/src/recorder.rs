@@ -0,0 +1,246 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::str::FromStr;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::Simulation;
+
+/// something the recorder can sample
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    FoodDelivered,
+    FoodPickedUp,
+    AntsCarrying,
+    /// single pheromones of all groups still alive
+    ActivePheromones,
+    /// to the closest nest
+    MeanNestDistance,
+    /// average duration of every profiler span in microseconds, one column per span.
+    /// empty unless the profiler of the simulation is enabled
+    PhaseTimings,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 6] = [
+        Metric::FoodDelivered,
+        Metric::FoodPickedUp,
+        Metric::AntsCarrying,
+        Metric::ActivePheromones,
+        Metric::MeanNestDistance,
+        Metric::PhaseTimings,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Metric::FoodDelivered => "food_delivered",
+            Metric::FoodPickedUp => "food_picked_up",
+            Metric::AntsCarrying => "ants_carrying",
+            Metric::ActivePheromones => "active_pheromones",
+            Metric::MeanNestDistance => "mean_nest_distance",
+            Metric::PhaseTimings => "phase_timings",
+        }
+    }
+}
+
+impl FromStr for Metric {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Metric::ALL
+            .into_iter()
+            .find(|metric| metric.name() == name)
+            .ok_or_else(|| {
+                format!(
+                    "unknown metric {}, expected one of {}",
+                    name,
+                    Metric::ALL.iter().map(Metric::name).join(", ")
+                )
+            })
+    }
+}
+
+/// samples metrics of a simulation every few ticks into a time series
+pub struct Recorder {
+    metrics: Vec<Metric>,
+    interval: usize,
+    samples: Vec<Sample>,
+}
+
+/// all metrics at one tick
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    pub tick: usize,
+    /// column name and value
+    pub values: Vec<(String, f64)>,
+}
+
+impl Recorder {
+    /// samples every `interval` ticks
+    pub fn new(metrics: Vec<Metric>, interval: usize) -> Recorder {
+        assert!(interval > 0, "Interval has to be at least one tick");
+
+        Recorder {
+            metrics,
+            interval,
+            samples: vec![],
+        }
+    }
+
+    /// call after every step, only samples on the ticks matching the interval
+    pub fn record(&mut self, simulation: &Simulation) {
+        let tick = simulation.stats().step_count;
+
+        if !tick.is_multiple_of(self.interval) {
+            return;
+        }
+
+        let mut values = vec![];
+
+        for metric in &self.metrics {
+            let value = match metric {
+                Metric::FoodDelivered => simulation.stats().dropped_of_food as f64,
+                Metric::FoodPickedUp => simulation.stats().picked_up_food as f64,
+                Metric::AntsCarrying => simulation
+                    .ants()
+                    .carried_foods()
+                    .iter()
+                    .filter(|count| **count > 0)
+                    .count() as f64,
+                Metric::ActivePheromones => simulation
+                    .pheromones()
+                    .groups
+                    .iter()
+                    .flatten()
+                    .map(|group| group.positions.len())
+                    .sum::<usize>() as f64,
+                Metric::MeanNestDistance => {
+                    let ants = simulation.ants();
+                    let total = ants
+                        .positions()
+                        .iter()
+                        .map(|pos| simulation.scenario().closest_nest(*pos).1.sqrt() as f64)
+                        .sum::<f64>();
+
+                    total / ants.len().max(1) as f64
+                }
+                Metric::PhaseTimings => {
+                    for span in simulation.profiler().spans() {
+                        values.push((
+                            format!("{}_us", span.name),
+                            span.timing.average().as_secs_f64() * 1_000_000.,
+                        ));
+                    }
+                    continue;
+                }
+            };
+
+            values.push((metric.name().to_string(), value));
+        }
+
+        self.samples.push(Sample { tick, values });
+    }
+
+    pub fn samples(&self) -> &[Sample] {
+        &self.samples
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// csv for paths ending in .csv, json lines otherwise
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+
+        if path.ends_with(".csv") {
+            self.write_csv(writer)
+        } else {
+            self.write_json_lines(writer)
+        }
+    }
+
+    /// one flat json object per sample
+    pub fn write_json_lines(&self, mut writer: impl Write) -> io::Result<()> {
+        for sample in &self.samples {
+            let values = sample.values.iter().map(|(name, value)| {
+                // json has no NaN or infinity
+                if value.is_finite() {
+                    format!(",\"{}\":{}", name, value)
+                } else {
+                    format!(",\"{}\":null", name)
+                }
+            });
+
+            writeln!(writer, "{{\"tick\":{}{}}}", sample.tick, values.format(""))?;
+        }
+
+        writer.flush()
+    }
+
+    /// one row per sample, columns appearing later on are left empty before
+    pub fn write_csv(&self, mut writer: impl Write) -> io::Result<()> {
+        let columns = self
+            .samples
+            .iter()
+            .flat_map(|sample| sample.values.iter().map(|(name, _)| name.as_str()))
+            .unique()
+            .collect_vec();
+
+        writeln!(writer, "tick,{}", columns.iter().join(","))?;
+
+        for sample in &self.samples {
+            let row = columns.iter().map(|column| {
+                sample
+                    .values
+                    .iter()
+                    .find(|(name, _)| name == column)
+                    .map_or(String::new(), |(_, value)| value.to_string())
+            });
+
+            writeln!(writer, "{},{}", sample.tick, row.format(","))?;
+        }
+
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::recorder::{Metric, Recorder};
+    use crate::Simulation;
+
+    #[test]
+    fn samples_every_interval() {
+        let mut simulation = Simulation::zero();
+        simulation.profiler_mut().set_enabled(true);
+        let mut recorder = Recorder::new(vec![Metric::AntsCarrying, Metric::PhaseTimings], 5);
+
+        for _ in 0..20 {
+            simulation.step();
+            recorder.record(&simulation);
+        }
+
+        let ticks = recorder.samples().iter().map(|sample| sample.tick);
+        assert!(ticks.eq([5, 10, 15, 20]));
+
+        let mut csv = vec![];
+        recorder.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+
+        let header = csv.lines().next().unwrap();
+        assert!(header.starts_with("tick,ants_carrying,step_us,"));
+        // spawning pheromones only shows up on tick 10
+        assert!(header.contains("spawn_pheromones_us"));
+        assert_eq!(csv.lines().count(), 5);
+        assert!(csv.lines().nth(1).unwrap().ends_with(','));
+
+        let mut json = vec![];
+        recorder.write_json_lines(&mut json).unwrap();
+        let json = String::from_utf8(json).unwrap();
+        assert!(json.starts_with("{\"tick\":5,\"ants_carrying\":0,\"step_us\":"));
+        assert_eq!(json.lines().count(), 4);
+    }
+}
@@ -0,0 +1,204 @@
+use std::f32::consts::PI;
+
+use glam::{vec2, Vec2};
+use serde::{Deserialize, Serialize};
+
+/// environment settings of a simulation, everything not set in a scenario file uses the default
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Scenario {
+    /// ants spawned at the start
+    pub ant_count: usize,
+    /// dropped food goes to a touching ant with free capacity instead of the ground
+    pub food_handover: bool,
+    /// ants push each other apart and slow down when crowding the nest
+    pub ant_collision: bool,
+    /// ants get spawned evenly across all nests and deliver to the closest one
+    pub nests: Vec<Vec2>,
+    /// always day if not set
+    pub day_night: Option<DayNight>,
+    /// no wind if not set
+    pub wind: Option<Wind>,
+    /// 0 spreads the starting directions of the ants evenly, anything else shuffles them
+    pub seed: u64,
+}
+
+/// a day starts and ends at midnight, noon is at half of `length`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct DayNight {
+    /// ticks per day
+    pub length: usize,
+    /// share of the see distance left at midnight
+    pub night_see_distance: f32,
+    /// share of the evaporation speed left at midnight
+    pub night_evaporation: f32,
+    /// food can be picked up while it is dark
+    pub food_at_night: bool,
+}
+
+impl Default for DayNight {
+    fn default() -> Self {
+        DayNight {
+            length: 3600,
+            night_see_distance: 0.3,
+            night_evaporation: 0.5,
+            food_at_night: true,
+        }
+    }
+}
+
+impl DayNight {
+    /// between 0 and 1, 0 = midnight, 0.5 = noon
+    pub fn time_of_day(&self, tick: usize) -> f32 {
+        (tick % self.length) as f32 / self.length as f32
+    }
+
+    /// 0 at midnight, 1 at noon
+    pub fn daylight(&self, tick: usize) -> f32 {
+        0.5 - (self.time_of_day(tick) * PI * 2.).cos() / 2.
+    }
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Scenario {
+            ant_count: 200,
+            food_handover: false,
+            ant_collision: false,
+            nests: vec![Vec2::ZERO],
+            day_night: None,
+            wind: None,
+            seed: 0,
+        }
+    }
+}
+
+impl Scenario {
+    /// index of the closest nest and the squared distance to it
+    pub fn closest_nest(&self, pos: Vec2) -> (usize, f32) {
+        self.nests
+            .iter()
+            .map(|nest| nest.distance_squared(pos))
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .expect("Scenario has no nests")
+    }
+}
+
+/// drifts the pheromones and pushes the ants a bit
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Wind {
+    /// units per second, the same everywhere on the map
+    pub base: Vec2,
+    /// units per second of the swirls on top of `base`, 0 = no swirls
+    pub swirl: f32,
+    /// distance between two swirls
+    pub swirl_size: f32,
+    /// share of the wind moving the ants
+    pub ant_drift: f32,
+}
+
+impl Default for Wind {
+    fn default() -> Self {
+        Wind {
+            base: vec2(10., 0.),
+            swirl: 0.,
+            swirl_size: 200.,
+            ant_drift: 0.1,
+        }
+    }
+}
+
+impl Wind {
+    /// units per second at `pos`
+    pub fn at(&self, pos: Vec2) -> Vec2 {
+        self.base
+            + vec2(
+                (pos.y / self.swirl_size).sin(),
+                (pos.x / self.swirl_size).cos(),
+            ) * self.swirl
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::castes::Caste;
+    use crate::scenario::{DayNight, Scenario, Wind};
+    use crate::Simulation;
+
+    #[test]
+    fn daylight_peaks_at_noon() {
+        let day_night = DayNight {
+            length: 100,
+            ..Default::default()
+        };
+
+        assert_eq!(day_night.daylight(0), 0.);
+        assert_eq!(day_night.daylight(50), 1.);
+        assert_eq!(day_night.daylight(100), 0.);
+        assert!(day_night.daylight(25) > 0.4 && day_night.daylight(25) < 0.6);
+    }
+
+    #[test]
+    fn seeds_shuffle_directions() {
+        let directions = |seed| {
+            let scenario = Scenario {
+                seed,
+                ..Default::default()
+            };
+            Simulation::with_scenario(scenario, vec![Caste::default()])
+                .ants()
+                .dirs()
+                .to_vec()
+        };
+
+        assert_eq!(directions(1), directions(1));
+        assert_ne!(directions(1), directions(2));
+        assert_ne!(directions(0), directions(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Days need at least one tick")]
+    fn rejects_empty_days() {
+        let scenario = Scenario {
+            day_night: Some(DayNight {
+                length: 0,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        Simulation::with_scenario(scenario, vec![Caste::default()]);
+    }
+
+    #[test]
+    #[should_panic(expected = "between 0 and 1")]
+    fn rejects_brighter_nights() {
+        let scenario = Scenario {
+            day_night: Some(DayNight {
+                night_see_distance: 1.5,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        Simulation::with_scenario(scenario, vec![Caste::default()]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Wind swirls need a positive size")]
+    fn rejects_swirls_without_size() {
+        let scenario = Scenario {
+            wind: Some(Wind {
+                swirl: 20.,
+                swirl_size: 0.,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        Simulation::with_scenario(scenario, vec![Caste::default()]);
+    }
+}
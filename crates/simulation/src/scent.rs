@@ -0,0 +1,124 @@
+use glam::Vec2;
+
+use crate::food::Food;
+use crate::grid::Grid;
+use crate::math::bilinear_cells;
+
+/// cells per side of the scent field, coarse on purpose as scent only needs to give a direction
+pub const SCENT_FIELD_SIZE: usize = 50;
+/// food further away than this can't be smelled
+pub const SCENT_RADIUS: f32 = 300.;
+/// distance at which the scent of a food dropped to half
+pub const SCENT_HALF_DISTANCE: f32 = 50.;
+/// scent of a single food right at the food, keeps big patches from saturating the senses
+pub const SCENT_PER_FOOD: f32 = 0.001;
+/// ticks between recomputing the field, food doesn't change fast enough to do it every tick
+pub const SCENT_UPDATE_TICKS: usize = 60;
+
+/// diffuse smell of all food, strongest right at the food and falling off with distance
+pub struct Scent {
+    field: Vec<f32>,
+    // food per cell, reused between updates
+    food_counts: Vec<usize>,
+    half_width: f32,
+}
+
+impl Scent {
+    pub fn new(half_width: f32) -> Scent {
+        Scent {
+            field: vec![0.; SCENT_FIELD_SIZE * SCENT_FIELD_SIZE],
+            food_counts: vec![0; SCENT_FIELD_SIZE * SCENT_FIELD_SIZE],
+            half_width,
+        }
+    }
+
+    pub(crate) fn update(&mut self, foods: &Grid<Food>) {
+        let cell_width = self.cell_width();
+
+        self.food_counts.fill(0);
+        for food in foods.iter() {
+            let cell = ((*food.pos() + self.half_width) / cell_width).floor();
+            let x = (cell.x.max(0.) as usize).min(SCENT_FIELD_SIZE - 1);
+            let y = (cell.y.max(0.) as usize).min(SCENT_FIELD_SIZE - 1);
+
+            self.food_counts[y * SCENT_FIELD_SIZE + x] += 1;
+        }
+
+        self.field.fill(0.);
+
+        let radius_cells = (SCENT_RADIUS / cell_width).ceil() as usize;
+
+        // food is clumped into few cells, so spreading out from those is cheap
+        for (source, count) in self.food_counts.iter().enumerate() {
+            if *count == 0 {
+                continue;
+            }
+
+            let source_x = source % SCENT_FIELD_SIZE;
+            let source_y = source / SCENT_FIELD_SIZE;
+
+            let min_x = source_x.saturating_sub(radius_cells);
+            let max_x = (source_x + radius_cells).min(SCENT_FIELD_SIZE - 1);
+            let min_y = source_y.saturating_sub(radius_cells);
+            let max_y = (source_y + radius_cells).min(SCENT_FIELD_SIZE - 1);
+
+            for y in min_y..=max_y {
+                for x in min_x..=max_x {
+                    let distance =
+                        Vec2::new(x as f32 - source_x as f32, y as f32 - source_y as f32).length()
+                            * cell_width;
+
+                    if distance > SCENT_RADIUS {
+                        continue;
+                    }
+
+                    let falloff = 1. / (1. + (distance / SCENT_HALF_DISTANCE).powi(2));
+                    self.field[y * SCENT_FIELD_SIZE + x] +=
+                        *count as f32 * SCENT_PER_FOOD * falloff;
+                }
+            }
+        }
+    }
+
+    /// scent at `pos`, interpolated between the surrounding field cells
+    pub fn sense(&self, pos: Vec2) -> f32 {
+        // relative to the cell centers
+        let cell = (pos + self.half_width) / self.cell_width() - 0.5;
+
+        bilinear_cells(cell, SCENT_FIELD_SIZE)
+            .into_iter()
+            .map(|(index, weight)| self.field[index] * weight)
+            .sum()
+    }
+
+    fn cell_width(&self) -> f32 {
+        self.half_width * 2. / SCENT_FIELD_SIZE as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec2;
+
+    use crate::food::Food;
+    use crate::grid::Grid;
+    use crate::scent::Scent;
+
+    #[test]
+    fn falls_off_with_distance() {
+        let mut foods = Grid::new(25, 500.);
+        let pos = vec2(200., 200.);
+        foods.insert(&pos, Food::new(pos));
+
+        let mut scent = Scent::new(500.);
+        scent.update(&foods);
+
+        let near = scent.sense(vec2(180., 200.));
+        let far = scent.sense(vec2(50., 200.));
+        let out_of_range = scent.sense(vec2(-300., -300.));
+
+        assert!(near > far);
+        assert!(far > 0.);
+        assert_eq!(out_of_range, 0.);
+    }
+}
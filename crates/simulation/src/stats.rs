@@ -0,0 +1,326 @@
+use glam::Vec2;
+use serde::Serialize;
+
+use crate::ants::AntId;
+
+/// an ant moving less than this share of its caste speed in a tick counts as idle
+pub const IDLE_SPEED_SHARE: f32 = 0.1;
+
+#[derive(Default, Serialize)]
+pub struct Stats {
+    pub step_count: usize,
+    pub picked_up_food: usize,
+    pub dropped_of_food: usize,
+    /// dropped outside of the nest
+    pub cached_food: usize,
+    /// picking up cached food does not count towards `picked_up_food`
+    pub picked_up_cached_food: usize,
+    pub handed_over_food: usize,
+    /// last step food got picked up, handed over or delivered
+    pub last_progress_step: usize,
+
+    // indexed by `AntId::raw`, despawned ants keep their record
+    #[serde(skip)]
+    ants: Vec<AntRecord>,
+    // indexed like `Scenario::nests`
+    nests: Vec<NestRecord>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NestRecord {
+    pub delivered_food: usize,
+    pub trips: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AntRecord {
+    pub trips: Vec<Trip>,
+    pub ticks: usize,
+    pub idle_ticks: usize,
+    pub carrying_ticks: usize,
+    /// ticks overlapping at least one other ant
+    pub crowded_ticks: usize,
+    pub path_length: f32,
+
+    current_trip: CurrentTrip,
+    last_pos: Vec2,
+}
+
+/// a round trip ending with food being delivered to the nest
+#[derive(Debug, Clone, Serialize)]
+pub struct Trip {
+    pub ticks: usize,
+    pub path_length: f32,
+    /// start -> first pick up -> delivery as straight lines
+    pub straight_length: f32,
+}
+
+#[derive(Debug, Clone, Default)]
+struct CurrentTrip {
+    start_tick: usize,
+    start_pos: Vec2,
+    path_length: f32,
+    pick_up_pos: Option<Vec2>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ForagingSummary {
+    pub trips: usize,
+    pub mean_trip_ticks: Option<f32>,
+    pub median_trip_ticks: Option<f32>,
+    /// straight length / path length over all trips, 1 = perfectly straight
+    pub path_efficiency: Option<f32>,
+    pub deliveries_per_1000_ticks: f32,
+    /// share of all ant ticks
+    pub idle_share: f32,
+    /// share of all ant ticks
+    pub carrying_share: f32,
+    /// share of all ant ticks
+    pub crowded_share: f32,
+}
+
+impl Stats {
+    pub fn ant(&self, id: AntId) -> Option<&AntRecord> {
+        self.ants.get(id.raw())
+    }
+
+    pub fn ants(&self) -> &[AntRecord] {
+        &self.ants
+    }
+
+    pub fn nests(&self) -> &[NestRecord] {
+        &self.nests
+    }
+
+    pub fn trips(&self) -> impl Iterator<Item = &Trip> {
+        self.ants.iter().flat_map(|ant| &ant.trips)
+    }
+
+    pub(crate) fn add_nest(&mut self) {
+        self.nests.push(NestRecord::default());
+    }
+
+    pub(crate) fn add_ant(&mut self, id: AntId, pos: Vec2) {
+        assert_eq!(id.raw(), self.ants.len(), "Ant ids have to be dense");
+
+        self.ants.push(AntRecord {
+            current_trip: CurrentTrip {
+                start_tick: self.step_count,
+                start_pos: pos,
+                ..Default::default()
+            },
+            last_pos: pos,
+            ..Default::default()
+        });
+    }
+
+    pub(crate) fn track_ant(
+        &mut self,
+        id: AntId,
+        pos: Vec2,
+        speed: f32,
+        carries_food: bool,
+        crowded: bool,
+    ) {
+        let ant = &mut self.ants[id.raw()];
+
+        let distance = ant.last_pos.distance(pos);
+        ant.last_pos = pos;
+
+        ant.ticks += 1;
+        ant.path_length += distance;
+        ant.current_trip.path_length += distance;
+
+        // 60 = frame rate
+        if distance < speed / 60. * IDLE_SPEED_SHARE {
+            ant.idle_ticks += 1;
+        }
+
+        if carries_food {
+            ant.carrying_ticks += 1;
+        }
+
+        if crowded {
+            ant.crowded_ticks += 1;
+        }
+    }
+
+    pub(crate) fn pick_up(&mut self, id: AntId, pos: Vec2, cached: bool) {
+        if cached {
+            self.picked_up_cached_food += 1;
+        } else {
+            self.picked_up_food += 1;
+        }
+        self.last_progress_step = self.step_count;
+
+        let trip = &mut self.ants[id.raw()].current_trip;
+        trip.pick_up_pos.get_or_insert(pos);
+    }
+
+    /// the receiving ant continues the trip as if it picked up the food itself
+    pub(crate) fn hand_over(&mut self, to: AntId, pos: Vec2, count: usize) {
+        self.handed_over_food += count;
+        self.last_progress_step = self.step_count;
+
+        let trip = &mut self.ants[to.raw()].current_trip;
+        trip.pick_up_pos.get_or_insert(pos);
+    }
+
+    pub(crate) fn deliver(&mut self, id: AntId, nest: usize, pos: Vec2, count: usize) {
+        self.dropped_of_food += count;
+        self.nests[nest].delivered_food += count;
+        self.nests[nest].trips += 1;
+        self.last_progress_step = self.step_count;
+
+        let step_count = self.step_count;
+        let ant = &mut self.ants[id.raw()];
+        let trip = &ant.current_trip;
+
+        let pick_up_pos = trip.pick_up_pos.unwrap_or(pos);
+
+        ant.trips.push(Trip {
+            ticks: step_count - trip.start_tick,
+            path_length: trip.path_length,
+            straight_length: trip.start_pos.distance(pick_up_pos) + pick_up_pos.distance(pos),
+        });
+
+        ant.current_trip = CurrentTrip {
+            start_tick: step_count,
+            start_pos: pos,
+            ..Default::default()
+        };
+    }
+
+    pub fn foraging_summary(&self) -> ForagingSummary {
+        let mut trip_ticks = self.trips().map(|trip| trip.ticks).collect::<Vec<_>>();
+        trip_ticks.sort_unstable();
+
+        let mean_trip_ticks = (!trip_ticks.is_empty())
+            .then(|| trip_ticks.iter().sum::<usize>() as f32 / trip_ticks.len() as f32);
+
+        let median_trip_ticks = (!trip_ticks.is_empty()).then(|| {
+            let middle = trip_ticks.len() / 2;
+
+            if trip_ticks.len() % 2 == 0 {
+                (trip_ticks[middle - 1] + trip_ticks[middle]) as f32 / 2.
+            } else {
+                trip_ticks[middle] as f32
+            }
+        });
+
+        let path_length = self.trips().map(|trip| trip.path_length).sum::<f32>();
+        let straight_length = self.trips().map(|trip| trip.straight_length).sum::<f32>();
+
+        let ant_ticks = self.ants.iter().map(|ant| ant.ticks).sum::<usize>().max(1) as f32;
+
+        ForagingSummary {
+            trips: trip_ticks.len(),
+            mean_trip_ticks,
+            median_trip_ticks,
+            path_efficiency: (path_length > 0.).then(|| straight_length / path_length),
+            deliveries_per_1000_ticks: self.dropped_of_food as f32 * 1000.
+                / self.step_count.max(1) as f32,
+            idle_share: self.ants.iter().map(|ant| ant.idle_ticks).sum::<usize>() as f32
+                / ant_ticks,
+            carrying_share: self
+                .ants
+                .iter()
+                .map(|ant| ant.carrying_ticks)
+                .sum::<usize>() as f32
+                / ant_ticks,
+            crowded_share: self.ants.iter().map(|ant| ant.crowded_ticks).sum::<usize>() as f32
+                / ant_ticks,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec2;
+
+    use crate::ants::Ants;
+    use crate::scenario::Scenario;
+    use crate::stats::Stats;
+    use crate::Simulation;
+
+    #[test]
+    fn records_round_trip() {
+        let mut ants = Ants::default();
+        let mut stats = Stats::default();
+        stats.add_nest();
+
+        let id = ants.spawn(vec2(0., 0.), 0., 0);
+        stats.add_ant(id, vec2(0., 0.));
+
+        for x in 1..=10 {
+            stats.step_count += 1;
+            stats.track_ant(id, vec2(x as f32, 0.), 60., x > 5, false);
+
+            if x == 5 {
+                stats.pick_up(id, vec2(5., 0.), false);
+            }
+        }
+
+        for x in (0..10).rev() {
+            stats.step_count += 1;
+            stats.track_ant(id, vec2(x as f32, 0.), 60., true, false);
+        }
+
+        stats.deliver(id, 0, vec2(0., 0.), 1);
+
+        let record = stats.ant(id).unwrap();
+        assert_eq!(record.trips.len(), 1);
+        assert_eq!(record.trips[0].ticks, 20);
+        assert_eq!(record.trips[0].path_length, 20.);
+        assert_eq!(record.trips[0].straight_length, 10.);
+        assert_eq!(record.carrying_ticks, 15);
+
+        let summary = stats.foraging_summary();
+        assert_eq!(summary.trips, 1);
+        assert_eq!(summary.median_trip_ticks, Some(20.));
+        assert_eq!(summary.path_efficiency, Some(0.5));
+        assert_eq!(summary.deliveries_per_1000_ticks, 50.);
+    }
+
+    #[test]
+    fn delivers_to_closest_nest() {
+        let scenario = Scenario {
+            nests: vec![vec2(0., 0.), vec2(-300., -300.)],
+            ..Default::default()
+        };
+        let mut simulation = Simulation::with_scenario(scenario, vec![Default::default()]);
+
+        let id = simulation.spawn_ant(vec2(-300., -300.), 0., 0);
+        let index = simulation.ants.index_of(id).unwrap();
+        simulation.ants.carried_foods[index] = 1;
+
+        simulation.step();
+
+        assert_eq!(simulation.stats().nests()[0].delivered_food, 0);
+        assert_eq!(simulation.stats().nests()[1].delivered_food, 1);
+    }
+
+    #[test]
+    fn delivers_to_the_closest_of_overlapping_nests() {
+        let scenario = Scenario {
+            nests: vec![vec2(0., 0.), vec2(60., 0.), vec2(-300., -300.)],
+            ..Default::default()
+        };
+        let mut simulation = Simulation::with_scenario(scenario, vec![Default::default()]);
+
+        // inside both hills, closer to the second one
+        let id = simulation.spawn_ant(vec2(40., 0.), 0., 0);
+        let index = simulation.ants.index_of(id).unwrap();
+        simulation.ants.carried_foods[index] = 1;
+
+        simulation.step();
+
+        let delivered = simulation
+            .stats()
+            .nests()
+            .iter()
+            .map(|nest| nest.delivered_food)
+            .collect::<Vec<_>>();
+        assert_eq!(delivered, [0, 1, 0]);
+    }
+}
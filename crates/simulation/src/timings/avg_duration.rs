@@ -1,14 +1,84 @@
-use std::fmt::{Debug, Formatter};
+use std::collections::VecDeque;
+use std::fmt::{Debug, Display, Formatter};
 use std::time::Duration;
 
-#[derive(Default)]
+use serde::Serialize;
+
+/// durations kept for the percentiles if not set otherwise, a few seconds at 60 ticks per second
+pub const DEFAULT_WINDOW: usize = 240;
+
+/// exponential average plus the latest `window` durations for percentiles and spikes
 pub struct AvgDuration {
     duration: Duration,
+    count: usize,
+    window: usize,
+    recent: VecDeque<Duration>,
+}
+
+/// statistics over the window of an `AvgDuration`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct DurationSummary {
+    /// all durations ever added, not only the ones in the window
+    pub count: usize,
+    pub mean: Duration,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl Default for AvgDuration {
+    fn default() -> Self {
+        AvgDuration::with_window(DEFAULT_WINDOW)
+    }
 }
 
 impl AvgDuration {
+    pub fn with_window(window: usize) -> AvgDuration {
+        assert!(window > 0, "Window has to hold at least one duration");
+
+        AvgDuration {
+            duration: Duration::ZERO,
+            count: 0,
+            window,
+            recent: VecDeque::with_capacity(window),
+        }
+    }
+
     pub fn add(&mut self, duration: &Duration) {
         self.duration = avg_duration(&self.duration, duration);
+        self.count += 1;
+
+        if self.recent.len() == self.window {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(*duration);
+    }
+
+    /// exponential average, follows changes within about a second
+    pub fn average(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn summary(&self) -> DurationSummary {
+        if self.recent.is_empty() {
+            return DurationSummary::default();
+        }
+
+        let mut sorted = self.recent.iter().copied().collect::<Vec<_>>();
+        sorted.sort();
+
+        let percentile =
+            |percentile: f32| sorted[((sorted.len() - 1) as f32 * percentile).round() as usize];
+
+        DurationSummary {
+            count: self.count,
+            mean: sorted.iter().sum::<Duration>() / sorted.len() as u32,
+            p50: percentile(0.5),
+            p95: percentile(0.95),
+            p99: percentile(0.99),
+            max: sorted[sorted.len() - 1],
+        }
     }
 }
 
@@ -18,6 +88,41 @@ impl Debug for AvgDuration {
     }
 }
 
+impl Display for DurationSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "mean {:.2?} p50 {:.2?} p95 {:.2?} p99 {:.2?} max {:.2?}",
+            self.mean, self.p50, self.p95, self.p99, self.max
+        )
+    }
+}
+
 pub fn avg_duration(current: &Duration, new: &Duration) -> Duration {
     Duration::from_secs_f64((current.as_secs_f64() * 59. + new.as_secs_f64()) / 60.)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::timings::avg_duration::AvgDuration;
+
+    #[test]
+    fn shows_spikes() {
+        let mut timing = AvgDuration::with_window(100);
+
+        for tick in 0..150 {
+            // a slow tick every 10 ticks like the pheromone spawning
+            let millis = if tick % 10 == 0 { 50 } else { 1 };
+            timing.add(&Duration::from_millis(millis));
+        }
+
+        let summary = timing.summary();
+        assert_eq!(summary.count, 150);
+        assert_eq!(summary.p50, Duration::from_millis(1));
+        assert_eq!(summary.p95, Duration::from_millis(50));
+        assert_eq!(summary.max, Duration::from_millis(50));
+        assert!(summary.mean > Duration::from_millis(5));
+    }
+}
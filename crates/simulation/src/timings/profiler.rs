@@ -0,0 +1,238 @@
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::timings::avg_duration::{AvgDuration, DEFAULT_WINDOW};
+
+/// named, nested timing spans
+///
+/// a span started while another one is open becomes its child, spans with the same name and parent
+/// share their statistics. does nothing but a branch per span when disabled
+pub struct Profiler {
+    enabled: bool,
+    // durations kept per span for the percentiles
+    window: usize,
+    spans: Vec<SpanStats>,
+    // currently open spans, innermost last
+    open: Vec<usize>,
+    // only recorded on request, grows with every span
+    trace: Option<Vec<TraceEvent>>,
+    created: Instant,
+}
+
+/// statistics of all runs of one span
+pub struct SpanStats {
+    pub name: &'static str,
+    /// index into `Profiler::spans`
+    pub parent: Option<usize>,
+    pub depth: usize,
+    pub timing: AvgDuration,
+    /// since the first run, unlike the summary of `timing`
+    pub min: Duration,
+    last: Duration,
+}
+
+/// returned by `Profiler::start`, has to be handed back to `Profiler::end`
+#[must_use]
+pub struct Span(Option<(usize, Instant)>);
+
+/// a single finished span in the chrome trace event format
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceEvent {
+    pub name: &'static str,
+    /// always "X", a complete event with a duration
+    pub ph: &'static str,
+    /// start in microseconds since the profiler got created
+    pub ts: f64,
+    /// microseconds
+    pub dur: f64,
+    pub pid: usize,
+    pub tid: usize,
+}
+
+/// can be opened in chrome://tracing or perfetto once serialized to json
+#[derive(Debug, Clone, Serialize)]
+pub struct ChromeTrace<'a> {
+    #[serde(rename = "traceEvents")]
+    pub trace_events: &'a [TraceEvent],
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Profiler::new()
+    }
+}
+
+impl Profiler {
+    pub fn new() -> Profiler {
+        Profiler::with_window(DEFAULT_WINDOW)
+    }
+
+    pub fn with_window(window: usize) -> Profiler {
+        Profiler {
+            enabled: true,
+            window,
+            spans: vec![],
+            open: vec![],
+            trace: None,
+            created: Instant::now(),
+        }
+    }
+
+    pub fn disabled() -> Profiler {
+        Profiler {
+            enabled: false,
+            ..Profiler::new()
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// spans open while disabling are dropped
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.open.clear();
+    }
+
+    /// keeps every finished span for `chrome_trace` until disabled again
+    pub fn set_trace_enabled(&mut self, enabled: bool) {
+        self.trace = enabled.then(Vec::new);
+    }
+
+    #[inline]
+    pub fn start(&mut self, name: &'static str) -> Span {
+        if !self.enabled {
+            return Span(None);
+        }
+
+        let parent = self.open.last().copied();
+        let span = match self
+            .spans
+            .iter()
+            .position(|span| span.parent == parent && span.name == name)
+        {
+            Some(span) => span,
+            None => {
+                self.spans.push(SpanStats {
+                    name,
+                    parent,
+                    depth: parent.map_or(0, |parent| self.spans[parent].depth + 1),
+                    timing: AvgDuration::with_window(self.window),
+                    min: Duration::MAX,
+                    last: Duration::ZERO,
+                });
+                self.spans.len() - 1
+            }
+        };
+
+        self.open.push(span);
+
+        Span(Some((span, Instant::now())))
+    }
+
+    #[inline]
+    pub fn end(&mut self, span: Span) {
+        let Some((index, start)) = span.0 else {
+            return;
+        };
+
+        let duration = start.elapsed();
+
+        // the span got dropped by disabling the profiler in between
+        if self.open.last() != Some(&index) {
+            return;
+        }
+        self.open.pop();
+
+        let span = &mut self.spans[index];
+        span.add(duration);
+
+        if let Some(trace) = &mut self.trace {
+            trace.push(TraceEvent {
+                name: span.name,
+                ph: "X",
+                ts: start.duration_since(self.created).as_secs_f64() * 1_000_000.,
+                dur: duration.as_secs_f64() * 1_000_000.,
+                pid: 0,
+                tid: 0,
+            });
+        }
+    }
+
+    /// all spans ordered depth first, children directly after their parent
+    pub fn spans(&self) -> Vec<&SpanStats> {
+        let mut ordered = Vec::with_capacity(self.spans.len());
+        self.push_children(None, &mut ordered);
+        ordered
+    }
+
+    fn push_children<'a>(&'a self, parent: Option<usize>, ordered: &mut Vec<&'a SpanStats>) {
+        for (index, span) in self.spans.iter().enumerate() {
+            if span.parent == parent {
+                ordered.push(span);
+                self.push_children(Some(index), ordered);
+            }
+        }
+    }
+
+    /// statistics of the first span with this name
+    pub fn span(&self, name: &str) -> Option<&SpanStats> {
+        self.spans.iter().find(|span| span.name == name)
+    }
+
+    pub fn chrome_trace(&self) -> ChromeTrace<'_> {
+        ChromeTrace {
+            trace_events: self.trace.as_deref().unwrap_or_default(),
+        }
+    }
+}
+
+impl SpanStats {
+    fn add(&mut self, duration: Duration) {
+        self.timing.add(&duration);
+        self.min = self.min.min(duration);
+        self.last = duration;
+    }
+
+    pub fn last(&self) -> Duration {
+        self.last
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::timings::profiler::Profiler;
+
+    #[test]
+    fn nests_spans() {
+        let mut profiler = Profiler::new();
+        profiler.set_trace_enabled(true);
+
+        for _ in 0..3 {
+            let step = profiler.start("step");
+            for name in ["a", "b"] {
+                let span = profiler.start(name);
+                profiler.end(span);
+            }
+            profiler.end(step);
+        }
+
+        let spans = profiler.spans();
+        assert_eq!(
+            spans
+                .iter()
+                .map(|span| (span.name, span.depth, span.timing.summary().count))
+                .collect::<Vec<_>>(),
+            vec![("step", 0, 3), ("a", 1, 3), ("b", 1, 3)]
+        );
+        assert!(spans[0].timing.summary().max >= spans[1].timing.summary().max);
+        assert_eq!(profiler.chrome_trace().trace_events.len(), 9);
+
+        let mut disabled = Profiler::disabled();
+        let span = disabled.start("step");
+        disabled.end(span);
+        assert!(disabled.spans().is_empty());
+    }
+}
@@ -1,39 +1,90 @@
 use console::Term;
-use simulation::Simulation;
+use neural_network::NeuralNetwork;
+use simulation::castes::{Caste, DEFAULT_CASTE_NAME};
+use simulation::scenario::Scenario;
+use simulation::timings::profiler::Profiler;
+use simulation::{Simulation, NEURAL_NETWORK_INPUT_SIZE, NEURAL_NETWORK_OUTPUT_SIZE};
 use std::io;
 use std::time::Instant;
 
-pub fn benchmark() -> io::Result<()> {
+/// ticks per second needed to run at the frame rate
+const INTERACTIVE_TICKS_PER_SECOND: f32 = 60.;
+
+/// a random network grown like in the visualizer, a zero network would never forage
+pub fn mutated_castes() -> Vec<Caste> {
+    let mut network = NeuralNetwork::new(NEURAL_NETWORK_INPUT_SIZE, NEURAL_NETWORK_OUTPUT_SIZE);
+
+    for _ in 0..50 {
+        network.mutate_strucutre();
+    }
+
+    vec![Caste::new(DEFAULT_CASTE_NAME, network)]
+}
+
+/// `window` is the number of latest steps the timing percentiles are taken over
+pub fn benchmark(
+    castes: Vec<Caste>,
+    ant_count: usize,
+    iterations: usize,
+    steps: usize,
+    window: usize,
+) -> io::Result<()> {
     let term = Term::stdout();
-    term.write_line("starting Benchmark!")?;
+    term.write_line(&format!("starting Benchmark with {} ants!", ant_count))?;
 
-    let mut steps = 0;
+    let mut steps_done = 0;
     let mut elapesed = 0.;
+    let mut simulation = None;
 
-    const ITERATIONS: usize = 10;
-    const STEPS: usize = 50_000;
-
-    for _ in 0..ITERATIONS {
-        let mut simulation = Simulation::zero();
+    for _ in 0..iterations {
+        let scenario = Scenario {
+            ant_count,
+            ..Default::default()
+        };
+        let simulation = simulation.insert(Simulation::with_scenario(scenario, castes.clone()));
+        *simulation.profiler_mut() = Profiler::with_window(window);
 
         let start_time = Instant::now();
 
-        for _ in 0..STEPS {
+        for _ in 0..steps {
             simulation.step();
         }
 
-        steps += simulation.stats().step_count;
+        steps_done += simulation.stats().step_count;
         elapesed += start_time.elapsed().as_secs_f32();
 
         term.move_cursor_up(1)?;
         term.clear_line()?;
         term.write_line(&format!(
             "steps: {}% in {} seconds {} steps/second",
-            steps / STEPS * ITERATIONS,
+            steps_done * 100 / (steps * iterations),
             elapesed,
-            steps as f32 / elapesed
+            steps_done as f32 / elapesed
         ))?;
     }
 
+    // phases of the last iteration
+    if let Some(simulation) = simulation {
+        for span in simulation.profiler().spans() {
+            term.write_line(&format!(
+                "{}{}: {}",
+                "  ".repeat(span.depth + 1),
+                span.name,
+                span.timing.summary()
+            ))?;
+        }
+    }
+
+    let steps_per_second = steps_done as f32 / elapesed;
+    term.write_line(&format!(
+        "{} the interactive target of {} steps/second",
+        if steps_per_second >= INTERACTIVE_TICKS_PER_SECOND {
+            "reached"
+        } else {
+            "missed"
+        },
+        INTERACTIVE_TICKS_PER_SECOND
+    ))?;
+
     Ok(())
 }
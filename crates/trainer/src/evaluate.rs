@@ -0,0 +1,231 @@
+use std::fs::File;
+use std::io::{self, BufWriter};
+
+use console::Term;
+use rayon::prelude::*;
+use serde::Serialize;
+use simulation::castes::Caste;
+use simulation::episode::{EpisodePolicy, Termination};
+use simulation::scenario::Scenario;
+use simulation::stats::Stats;
+use simulation::timings::avg_duration::DurationSummary;
+use simulation::Simulation;
+
+use crate::train::Trainer;
+
+/// everything measured while evaluating a colony, written as json
+#[derive(Serialize)]
+pub struct EvaluationReport<'a> {
+    pub colony: &'a str,
+    pub max_steps: usize,
+    pub mean_fitness: f32,
+    pub min_fitness: f32,
+    pub max_fitness: f32,
+    pub mean_delivered_food: f32,
+    pub runs: Vec<RunReport<'a>>,
+}
+
+/// a single scenario and seed
+#[derive(Serialize)]
+pub struct RunReport<'a> {
+    pub scenario: &'a str,
+    pub seed: u64,
+    pub fitness: f32,
+    pub termination: Option<Termination>,
+    pub stats: &'a Stats,
+    pub timings: Vec<SpanReport>,
+}
+
+#[derive(Serialize)]
+pub struct SpanReport {
+    pub name: &'static str,
+    pub depth: usize,
+    pub summary: DurationSummary,
+}
+
+/// runs the colony on every scenario with the seeds `0..seeds`, seed 0 is the layout used while training
+///
+/// writes the report to `output` or prints it if not set
+pub fn evaluate(
+    colony: &str,
+    castes: Vec<Caste>,
+    scenarios: Vec<(String, Scenario)>,
+    seeds: u64,
+    episode: EpisodePolicy,
+    output: Option<String>,
+) -> io::Result<()> {
+    assert!(seeds > 0, "Need at least one seed");
+
+    let term = Term::stderr();
+    term.write_line(&format!(
+        "evaluating {} on {} scenarios with {} seeds",
+        colony,
+        scenarios.len(),
+        seeds
+    ))?;
+
+    let runs = run_seeds(&castes, &scenarios, seeds, &episode, true);
+    let runs = runs
+        .iter()
+        .map(|(scenario, seed, simulation)| RunReport {
+            scenario,
+            seed: *seed,
+            fitness: Trainer::eval(simulation, &episode),
+            termination: simulation.termination(&episode),
+            stats: simulation.stats(),
+            timings: simulation
+                .profiler()
+                .spans()
+                .into_iter()
+                .map(|span| SpanReport {
+                    name: span.name,
+                    depth: span.depth,
+                    summary: span.timing.summary(),
+                })
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+
+    let fitnesses = runs.iter().map(|run| run.fitness);
+    let report = EvaluationReport {
+        colony,
+        max_steps: episode.max_steps,
+        mean_fitness: fitnesses.clone().sum::<f32>() / runs.len() as f32,
+        min_fitness: fitnesses.clone().fold(f32::INFINITY, f32::min),
+        max_fitness: fitnesses.fold(f32::NEG_INFINITY, f32::max),
+        mean_delivered_food: runs
+            .iter()
+            .map(|run| run.stats.dropped_of_food as f32)
+            .sum::<f32>()
+            / runs.len() as f32,
+        runs,
+    };
+
+    for run in &report.runs {
+        term.write_line(&format!(
+            "{} seed {}: fitness {} delivered {} after {} steps",
+            run.scenario, run.seed, run.fitness, run.stats.dropped_of_food, run.stats.step_count
+        ))?;
+    }
+    term.write_line(&format!(
+        "mean fitness {} (min {} max {}) mean delivered {}",
+        report.mean_fitness, report.min_fitness, report.max_fitness, report.mean_delivered_food
+    ))?;
+
+    match output {
+        Some(path) => serde_json::to_writer_pretty(BufWriter::new(File::create(path)?), &report)?,
+        None => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+
+    Ok(())
+}
+
+/// one finished episode of the colony per scenario and seed, named after their scenario
+///
+/// `profile` enables the profiler of every run for its phase timings
+pub fn run_seeds<'a>(
+    castes: &[Caste],
+    scenarios: &'a [(String, Scenario)],
+    seeds: u64,
+    episode: &EpisodePolicy,
+    profile: bool,
+) -> Vec<(&'a str, u64, Simulation)> {
+    let mut runs = scenarios
+        .iter()
+        .flat_map(|(name, scenario)| {
+            (0..seeds).map(|seed| {
+                let scenario = Scenario {
+                    seed,
+                    ..scenario.clone()
+                };
+                (
+                    name.as_str(),
+                    seed,
+                    Simulation::with_scenario(scenario, castes.to_vec()),
+                )
+            })
+        })
+        .collect::<Vec<_>>();
+
+    runs.par_iter_mut().for_each(|(_, _, simulation)| {
+        simulation.profiler_mut().set_enabled(profile);
+        simulation.run_episode(episode);
+    });
+
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use simulation::castes::Caste;
+    use simulation::episode::EpisodePolicy;
+    use simulation::scenario::Scenario;
+
+    use crate::evaluate::evaluate;
+
+    #[test]
+    fn reports_every_seed() {
+        let path = std::env::temp_dir().join(format!("evaluate-{}.json", std::process::id()));
+        let scenario = Scenario {
+            ant_count: 10,
+            ..Default::default()
+        };
+        let episode = EpisodePolicy {
+            max_steps: 30,
+            ..Default::default()
+        };
+
+        evaluate(
+            "tiny.json",
+            vec![Caste::default()],
+            vec![("tiny".to_string(), scenario)],
+            2,
+            episode,
+            Some(path.display().to_string()),
+        )
+        .unwrap();
+
+        let report: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(report["colony"], "tiny.json");
+        assert_eq!(report["max_steps"], 30);
+
+        let runs = report["runs"].as_array().unwrap();
+        assert_eq!(runs.len(), 2);
+        for (seed, run) in runs.iter().enumerate() {
+            assert_eq!(run["scenario"], "tiny");
+            assert_eq!(run["seed"], seed);
+            assert_eq!(run["termination"], "MaxSteps");
+            assert_eq!(run["stats"]["step_count"], 30);
+            assert!(!run["timings"].as_array().unwrap().is_empty());
+        }
+
+        let field = |value: &serde_json::Value| value.as_f64().unwrap();
+        let fitnesses = runs
+            .iter()
+            .map(|run| field(&run["fitness"]))
+            .collect::<Vec<_>>();
+        let delivered = runs
+            .iter()
+            .map(|run| field(&run["stats"]["dropped_of_food"]))
+            .collect::<Vec<_>>();
+
+        assert!((field(&report["mean_fitness"]) - (fitnesses[0] + fitnesses[1]) / 2.).abs() < 1e-3);
+        assert_eq!(
+            field(&report["min_fitness"]),
+            fitnesses[0].min(fitnesses[1])
+        );
+        assert_eq!(
+            field(&report["max_fitness"]),
+            fitnesses[0].max(fitnesses[1])
+        );
+        assert_eq!(
+            field(&report["mean_delivered_food"]),
+            (delivered[0] + delivered[1]) / 2.
+        );
+    }
+}
@@ -1,9 +1,21 @@
-use crate::benchmark::benchmark;
+use crate::benchmark::{benchmark, mutated_castes};
 use clap::{Parser, Subcommand};
+use simulation::castes::{Caste, SavedColony};
+use simulation::episode::EpisodePolicy;
+use simulation::recorder::Metric;
+use simulation::scenario::Scenario;
+use std::fs::File;
+use std::io::BufReader;
 
-use crate::train::Trainer;
+use crate::evaluate::evaluate;
+use crate::nest_search::search_nests;
+use crate::tournament::tournament;
+use crate::train::{Trainer, TrainingOutputs};
 
 mod benchmark;
+mod evaluate;
+mod nest_search;
+mod tournament;
 mod train;
 
 const STEPS_PER_SIMULATION: usize = 5000;
@@ -18,10 +30,106 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
-    Benchmark,
+    Benchmark {
+        #[arg(short, long, default_value_t = 200)]
+        ants: usize,
+        #[arg(short, long, default_value_t = 10)]
+        iterations: usize,
+        #[arg(short, long, default_value_t = 50_000)]
+        steps: usize,
+        /// latest steps the timing percentiles are taken over
+        #[arg(short, long, default_value_t = 1000)]
+        window: usize,
+        /// saved colony to run, a randomly mutated network if not set
+        #[arg(long)]
+        colony: Option<String>,
+    },
     Learn {
         #[arg(short, long, default_value_t = 10)]
         count: usize,
+        /// json file listing the castes of the colony, a single default caste if not set
+        #[arg(long)]
+        castes: Option<String>,
+        /// json file with the scenario settings, defaults for everything not set
+        #[arg(long)]
+        scenario: Option<String>,
+        /// json file with the episode policy, episodes end after 5000 steps or once all food is delivered if not set
+        #[arg(long)]
+        episode: Option<String>,
+        /// writes a chrome trace json of the best colony after every generation
+        #[arg(long)]
+        trace: Option<String>,
+        /// writes the metrics of the best colony after every generation, csv or json lines
+        #[arg(long)]
+        record: Option<String>,
+        /// comma separated, everything if not set
+        #[arg(long, value_delimiter = ',')]
+        metrics: Vec<Metric>,
+        /// ticks between two samples of the metrics
+        #[arg(long, default_value_t = 50)]
+        record_every: usize,
+    },
+    /// runs a saved colony without a window and reports its stats, fitness and timings as json
+    #[command(alias = "run")]
+    Evaluate {
+        /// json file of the colony
+        #[arg(short, long)]
+        path: String,
+        /// comma separated json files with scenario settings, the default scenario if not set
+        #[arg(long, value_delimiter = ',')]
+        scenarios: Vec<String>,
+        /// every scenario is run with the seeds 0 up to this
+        #[arg(long, default_value_t = 5)]
+        seeds: u64,
+        /// json file with the episode policy, same defaults as while learning
+        #[arg(long)]
+        episode: Option<String>,
+        /// overrides the max steps of the episode policy
+        #[arg(long)]
+        steps: Option<usize>,
+        /// writes the report into this file instead of printing it
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// re-evaluates many saved colonies on the same scenarios and seeds and ranks them
+    Tournament {
+        /// colony files or directories containing them, like ./training
+        #[arg(required = true)]
+        paths: Vec<String>,
+        /// comma separated json files with scenario settings, the default scenario if not set
+        #[arg(long, value_delimiter = ',')]
+        scenarios: Vec<String>,
+        /// every scenario is run with the seeds 0 up to this
+        #[arg(long, default_value_t = 10)]
+        seeds: u64,
+        /// json file with the episode policy, same defaults as while learning
+        #[arg(long)]
+        episode: Option<String>,
+        /// overrides the max steps of the episode policy
+        #[arg(long)]
+        steps: Option<usize>,
+        /// writes the leaderboard into this file, csv if it ends with .csv and markdown otherwise
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// searches the nest positions delivering the most food for a fixed colony
+    Nests {
+        /// json file of the colony to place nests for
+        #[arg(short, long)]
+        path: String,
+        #[arg(short, long, default_value_t = 2)]
+        count: usize,
+        #[arg(short, long, default_value_t = 50)]
+        rounds: usize,
+        /// json file with the other scenario settings, its nests are the starting layout
+        #[arg(long)]
+        scenario: Option<String>,
+        /// json file with the episode policy, same defaults as while learning
+        #[arg(long)]
+        episode: Option<String>,
+        /// overrides the max steps of the episode policy
+        #[arg(long)]
+        steps: Option<usize>,
     },
 }
 
@@ -29,7 +137,187 @@ fn main() {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Learn { count } => Trainer::new(count, 10).train().unwrap(),
-        Commands::Benchmark => benchmark().unwrap(),
+        Commands::Learn {
+            count,
+            castes,
+            scenario,
+            episode,
+            trace,
+            record,
+            metrics,
+            record_every,
+        } => {
+            let castes = castes
+                .map(|path| load_castes(&path))
+                .unwrap_or_else(|| vec![Caste::default()]);
+
+            Trainer::new(
+                count,
+                10,
+                castes,
+                load_scenario(scenario),
+                load_episode(episode),
+                TrainingOutputs {
+                    trace,
+                    record,
+                    metrics: if metrics.is_empty() {
+                        Metric::ALL.to_vec()
+                    } else {
+                        metrics
+                    },
+                    record_every,
+                },
+            )
+            .train()
+            .unwrap()
+        }
+        Commands::Evaluate {
+            path,
+            scenarios,
+            seeds,
+            episode,
+            steps,
+            output,
+        } => evaluate(
+            &path,
+            load_castes(&path),
+            load_scenarios(scenarios),
+            seeds,
+            load_episode_with_steps(episode, steps),
+            output,
+        )
+        .unwrap(),
+        Commands::Tournament {
+            paths,
+            scenarios,
+            seeds,
+            episode,
+            steps,
+            output,
+        } => tournament(
+            paths,
+            load_scenarios(scenarios),
+            seeds,
+            load_episode_with_steps(episode, steps),
+            output,
+        )
+        .unwrap(),
+        Commands::Nests {
+            path,
+            count,
+            rounds,
+            scenario,
+            episode,
+            steps,
+        } => search_nests(
+            load_castes(&path),
+            load_scenario(scenario),
+            load_episode_with_steps(episode, steps),
+            count,
+            rounds,
+        )
+        .unwrap(),
+        Commands::Benchmark {
+            ants,
+            iterations,
+            steps,
+            window,
+            colony,
+        } => benchmark(
+            colony.map_or_else(mutated_castes, |path| load_castes(&path)),
+            ants,
+            iterations,
+            steps,
+            window,
+        )
+        .unwrap(),
+    }
+}
+
+fn load_castes(path: &str) -> Vec<Caste> {
+    let file = File::open(path).unwrap();
+    let reader = BufReader::new(file);
+    serde_json::from_reader::<_, SavedColony>(reader)
+        .unwrap()
+        .into_castes()
+}
+
+fn load_scenario(path: Option<String>) -> Scenario {
+    path.map(|path| {
+        let file = File::open(path).unwrap();
+        let reader = BufReader::new(file);
+        serde_json::from_reader::<_, Scenario>(reader).unwrap()
+    })
+    .unwrap_or_default()
+}
+
+fn load_episode(path: Option<String>) -> EpisodePolicy {
+    path.map(|path| {
+        let file = File::open(path).unwrap();
+        let reader = BufReader::new(file);
+        serde_json::from_reader::<_, EpisodePolicy>(reader).unwrap()
+    })
+    .unwrap_or(EpisodePolicy {
+        max_steps: STEPS_PER_SIMULATION,
+        ..Default::default()
+    })
+}
+
+/// named after their files, only the default scenario if there are none
+fn load_scenarios(paths: Vec<String>) -> Vec<(String, Scenario)> {
+    if paths.is_empty() {
+        return vec![("default".to_string(), Scenario::default())];
+    }
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let scenario = load_scenario(Some(path.clone()));
+            (path, scenario)
+        })
+        .collect()
+}
+
+fn load_episode_with_steps(path: Option<String>, steps: Option<usize>) -> EpisodePolicy {
+    let mut episode = load_episode(path);
+    if let Some(steps) = steps {
+        episode.max_steps = steps;
+    }
+
+    episode
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use simulation::scenario::{DayNight, Scenario};
+
+    use crate::load_scenario;
+
+    #[test]
+    fn missing_scenario_fields_use_the_defaults() {
+        let path = std::env::temp_dir().join(format!("scenario-{}.json", std::process::id()));
+        fs::write(
+            &path,
+            r#"{ "ant_count": 5, "food_handover": true, "day_night": { "length": 100 } }"#,
+        )
+        .unwrap();
+
+        let scenario = load_scenario(Some(path.display().to_string()));
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(
+            scenario,
+            Scenario {
+                ant_count: 5,
+                food_handover: true,
+                day_night: Some(DayNight {
+                    length: 100,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }
+        );
     }
 }
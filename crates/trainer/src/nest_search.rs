@@ -0,0 +1,164 @@
+use std::{fs, io, time::Instant};
+
+use chrono::Local;
+use console::Term;
+use fancy_duration::AsFancyDuration;
+use glam::{vec2, Vec2};
+use itertools::Itertools;
+use rand::{thread_rng, Rng};
+use rayon::prelude::*;
+use simulation::castes::Caste;
+use simulation::episode::EpisodePolicy;
+use simulation::scenario::Scenario;
+use simulation::{Simulation, ANT_HILL_RADIUS, GAME_SIZE};
+
+/// layouts evaluated per round
+const CANDIDATES_PER_ROUND: usize = 16;
+/// how far a nest can get moved when mutating the best layout
+const MAX_NEST_OFFSET: f32 = 100.;
+/// nests have to fit inside the map
+const MAX_NEST_POS: f32 = GAME_SIZE - ANT_HILL_RADIUS;
+
+/// hill climbing over nest positions with fixed castes, maximising the delivered food
+pub fn search_nests(
+    castes: Vec<Caste>,
+    scenario: Scenario,
+    episode: EpisodePolicy,
+    nest_count: usize,
+    rounds: usize,
+) -> io::Result<()> {
+    assert!(nest_count > 0, "Need at least one nest");
+
+    let term = Term::stdout();
+    term.write_line("starting nest search")?;
+
+    let start_time = Instant::now();
+    let mut rng = thread_rng();
+
+    // the layout of the scenario is the starting point if it fits
+    let mut best = scenario.clone();
+    if best.nests.len() != nest_count {
+        best.nests = (0..nest_count).map(|_| random_nest(&mut rng)).collect();
+    }
+    let mut best_score = evaluate(&castes, &best, &episode);
+
+    for round in 1..=rounds {
+        let candidates = (0..CANDIDATES_PER_ROUND)
+            .map(|_| Scenario {
+                nests: best
+                    .nests
+                    .iter()
+                    .map(|nest| mutate_nest(*nest, &mut rng))
+                    .collect(),
+                ..best.clone()
+            })
+            .collect_vec();
+
+        let scores = candidates
+            .par_iter()
+            .map(|candidate| evaluate(&castes, candidate, &episode))
+            .collect::<Vec<_>>();
+
+        if let Some((candidate, score)) = candidates
+            .into_iter()
+            .zip(scores)
+            .max_by_key(|(_, score)| *score)
+        {
+            if score > best_score {
+                best = candidate;
+                best_score = score;
+
+                save_layout(&best, best_score);
+            }
+        }
+
+        term.clear_line()?;
+        term.write_line(&format!(
+            "round: {} | best delivered: {} | nests: {} | time: {}",
+            round,
+            best_score,
+            best.nests
+                .iter()
+                .map(|nest| format!("({:.0}, {:.0})", nest.x, nest.y))
+                .join(" "),
+            start_time.elapsed().fancy_duration().truncate(2)
+        ))?;
+        term.move_cursor_up(1)?;
+    }
+
+    term.move_cursor_down(1)?;
+    term.write_line(&serde_json::to_string(&best).unwrap())?;
+
+    Ok(())
+}
+
+fn evaluate(castes: &[Caste], scenario: &Scenario, episode: &EpisodePolicy) -> usize {
+    let mut simulation = Simulation::with_scenario(scenario.clone(), castes.to_vec());
+    simulation.run_episode(episode);
+
+    simulation.stats().dropped_of_food
+}
+
+fn random_nest(rng: &mut impl Rng) -> Vec2 {
+    vec2(
+        rng.gen_range(-MAX_NEST_POS..MAX_NEST_POS),
+        rng.gen_range(-MAX_NEST_POS..MAX_NEST_POS),
+    )
+}
+
+fn mutate_nest(nest: Vec2, rng: &mut impl Rng) -> Vec2 {
+    let offset = vec2(
+        rng.gen_range(-MAX_NEST_OFFSET..MAX_NEST_OFFSET),
+        rng.gen_range(-MAX_NEST_OFFSET..MAX_NEST_OFFSET),
+    );
+
+    (nest + offset).clamp(Vec2::splat(-MAX_NEST_POS), Vec2::splat(MAX_NEST_POS))
+}
+
+fn save_layout(scenario: &Scenario, score: usize) {
+    let path_string = format!(
+        "./training/nests-{}-{}.json",
+        Local::now().format("%Y-%m-%d_%H-%M-%S"),
+        score
+    );
+    let path = std::path::Path::new(&path_string);
+    let prefix = path.parent().unwrap();
+    std::fs::create_dir_all(prefix).unwrap();
+
+    fs::write(path, serde_json::to_string(scenario).unwrap()).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::nest_search::{mutate_nest, random_nest, MAX_NEST_OFFSET, MAX_NEST_POS};
+
+    fn fits_the_map(nest: Vec2) -> bool {
+        nest.abs().max_element() <= MAX_NEST_POS
+    }
+
+    #[test]
+    fn random_nests_fit_the_map() {
+        let mut rng = StdRng::seed_from_u64(1);
+
+        assert!((0..1000).all(|_| fits_the_map(random_nest(&mut rng))));
+    }
+
+    #[test]
+    fn mutated_nests_stay_inside_the_map() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut nest = Vec2::new(MAX_NEST_POS, -MAX_NEST_POS);
+
+        for _ in 0..1000 {
+            let mutated = mutate_nest(nest, &mut rng);
+
+            assert!(fits_the_map(mutated), "Nest {} left the map", mutated);
+            assert!((mutated - nest).abs().max_element() <= MAX_NEST_OFFSET);
+
+            nest = mutated;
+        }
+    }
+}
@@ -0,0 +1,342 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use console::Term;
+use itertools::Itertools;
+use simulation::castes::{Caste, SavedColony};
+use simulation::episode::EpisodePolicy;
+use simulation::scenario::Scenario;
+use simulation::{NEURAL_NETWORK_INPUT_SIZE, NEURAL_NETWORK_OUTPUT_SIZE};
+
+use crate::evaluate::run_seeds;
+use crate::train::Trainer;
+
+/// z score of a two sided 95% confidence interval
+const CONFIDENCE_Z: f32 = 1.96;
+
+/// results of one network over all scenarios and seeds
+pub struct Entry {
+    pub path: String,
+    pub runs: usize,
+    pub mean_fitness: f32,
+    /// half the width of the 95% confidence interval of the mean fitness
+    pub confidence: f32,
+    pub min_fitness: f32,
+    pub max_fitness: f32,
+    pub mean_delivered_food: f32,
+}
+
+/// evaluates every colony on the same scenarios and seeds and ranks them by their mean fitness
+///
+/// `paths` can be colony files or directories, files in them which are no colony are skipped.
+/// the leaderboard is written as csv if `output` ends with .csv, as a markdown table otherwise
+pub fn tournament(
+    paths: Vec<String>,
+    scenarios: Vec<(String, Scenario)>,
+    seeds: u64,
+    episode: EpisodePolicy,
+    output: Option<String>,
+) -> io::Result<()> {
+    assert!(seeds > 0, "Need at least one seed");
+
+    let term = Term::stderr();
+
+    let files = collect_files(&paths)?;
+    let mut colonies = vec![];
+    for file in files {
+        match load_colony(&file) {
+            Ok(castes) => colonies.push((file.display().to_string(), castes)),
+            Err(error) => term.write_line(&format!("skipping {}: {}", file.display(), error))?,
+        }
+    }
+
+    term.write_line(&format!(
+        "evaluating {} networks on {} scenarios with {} seeds",
+        colonies.len(),
+        scenarios.len(),
+        seeds
+    ))?;
+    term.write_line("")?;
+
+    let count = colonies.len();
+    let mut entries = vec![];
+    for (i, (path, castes)) in colonies.into_iter().enumerate() {
+        term.move_cursor_up(1)?;
+        term.clear_line()?;
+        term.write_line(&format!("network {}/{}: {}", i + 1, count, path))?;
+
+        let runs = run_seeds(&castes, &scenarios, seeds, &episode, false);
+        let fitnesses = runs
+            .iter()
+            .map(|(_, _, simulation)| Trainer::eval(simulation, &episode))
+            .collect_vec();
+        let delivered = runs
+            .iter()
+            .map(|(_, _, simulation)| simulation.stats().dropped_of_food as f32)
+            .collect_vec();
+
+        let (mean_fitness, deviation) = mean_and_deviation(&fitnesses);
+        entries.push(Entry {
+            path,
+            runs: fitnesses.len(),
+            mean_fitness,
+            confidence: CONFIDENCE_Z * deviation / (fitnesses.len() as f32).sqrt(),
+            min_fitness: fitnesses.iter().copied().fold(f32::INFINITY, f32::min),
+            max_fitness: fitnesses.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+            mean_delivered_food: mean_and_deviation(&delivered).0,
+        });
+    }
+
+    entries.sort_by(|a, b| b.mean_fitness.total_cmp(&a.mean_fitness));
+
+    let table = markdown_table(&entries);
+    println!("{}", table);
+
+    if let Some(output) = output {
+        let mut file = File::create(&output)?;
+        if output.ends_with(".csv") {
+            write_csv(&entries, &mut file)?;
+        } else {
+            file.write_all(table.as_bytes())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// json files directly inside the directories and the files themselves, sorted
+fn collect_files(paths: &[String]) -> io::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+
+    for path in paths {
+        let path = Path::new(path);
+
+        if path.is_dir() {
+            for entry in fs::read_dir(path)? {
+                let file = entry?.path();
+                if file
+                    .extension()
+                    .is_some_and(|extension| extension == "json")
+                {
+                    files.push(file);
+                }
+            }
+        } else {
+            files.push(path.to_path_buf());
+        }
+    }
+
+    files.sort();
+    files.dedup();
+
+    Ok(files)
+}
+
+/// colonies the simulation would reject, like ones saved before the network inputs changed, are errors
+fn load_colony(path: &Path) -> Result<Vec<Caste>, String> {
+    let file = File::open(path).map_err(|error| error.to_string())?;
+    let castes = serde_json::from_reader::<_, SavedColony>(BufReader::new(file))
+        .map_err(|error| error.to_string())?
+        .into_castes();
+
+    if castes.is_empty() {
+        return Err("no castes".to_string());
+    }
+
+    for caste in &castes {
+        let network = &caste.neural_network;
+
+        if network.get_input_size() != NEURAL_NETWORK_INPUT_SIZE
+            || network.get_output_size() != NEURAL_NETWORK_OUTPUT_SIZE
+        {
+            return Err(format!(
+                "caste {} has {} inputs and {} outputs instead of {} and {}",
+                caste.name,
+                network.get_input_size(),
+                network.get_output_size(),
+                NEURAL_NETWORK_INPUT_SIZE,
+                NEURAL_NETWORK_OUTPUT_SIZE
+            ));
+        }
+
+        if caste.carry_capacity == 0 || !(caste.ratio > 0. && caste.ratio.is_finite()) {
+            return Err(format!(
+                "caste {} needs a carry capacity and a positive ratio",
+                caste.name
+            ));
+        }
+    }
+
+    Ok(castes)
+}
+
+/// sample standard deviation, 0 for a single value
+fn mean_and_deviation(values: &[f32]) -> (f32, f32) {
+    let mean = values.iter().sum::<f32>() / values.len() as f32;
+
+    if values.len() < 2 {
+        return (mean, 0.);
+    }
+
+    let variance = values
+        .iter()
+        .map(|value| (value - mean) * (value - mean))
+        .sum::<f32>()
+        / (values.len() - 1) as f32;
+
+    (mean, variance.sqrt())
+}
+
+/// networks whose interval overlaps the one of the first are marked as tied with it
+fn markdown_table(entries: &[Entry]) -> String {
+    let best_lower = entries.first().map_or(f32::NEG_INFINITY, |best| {
+        best.mean_fitness - best.confidence
+    });
+
+    let mut table = String::from(
+        "| rank | network | fitness | 95% ci | min | max | delivered | tied with best |\n\
+         |---|---|---|---|---|---|---|---|\n",
+    );
+
+    for (i, entry) in entries.iter().enumerate() {
+        table += &format!(
+            "| {} | {} | {:.1} | ±{:.1} | {:.1} | {:.1} | {:.1} | {} |\n",
+            i + 1,
+            entry.path,
+            entry.mean_fitness,
+            entry.confidence,
+            entry.min_fitness,
+            entry.max_fitness,
+            entry.mean_delivered_food,
+            if entry.mean_fitness + entry.confidence >= best_lower {
+                "yes"
+            } else {
+                "no"
+            }
+        );
+    }
+
+    table
+}
+
+fn write_csv(entries: &[Entry], mut writer: impl Write) -> io::Result<()> {
+    writeln!(
+        writer,
+        "rank,network,runs,mean_fitness,confidence,min_fitness,max_fitness,mean_delivered_food"
+    )?;
+
+    for (i, entry) in entries.iter().enumerate() {
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{}",
+            i + 1,
+            csv_field(&entry.path),
+            entry.runs,
+            entry.mean_fitness,
+            entry.confidence,
+            entry.min_fitness,
+            entry.max_fitness,
+            entry.mean_delivered_food
+        )?;
+    }
+
+    Ok(())
+}
+
+/// quoted if it contains a separator, quote or line break, quotes inside are doubled
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use neural_network::NeuralNetwork;
+    use simulation::castes::{Caste, SavedColony};
+    use simulation::{NEURAL_NETWORK_INPUT_SIZE, NEURAL_NETWORK_OUTPUT_SIZE};
+
+    use crate::tournament::{
+        csv_field, load_colony, markdown_table, mean_and_deviation, write_csv, Entry,
+    };
+
+    fn entry(path: &str, mean_fitness: f32, confidence: f32) -> Entry {
+        Entry {
+            path: path.to_string(),
+            runs: 4,
+            mean_fitness,
+            confidence,
+            min_fitness: mean_fitness - 1.,
+            max_fitness: mean_fitness + 1.,
+            mean_delivered_food: 0.,
+        }
+    }
+
+    #[test]
+    fn mean_and_sample_deviation() {
+        let (mean, deviation) = mean_and_deviation(&[2., 4., 4., 4., 5., 5., 7., 9.]);
+        assert_eq!(mean, 5.);
+        assert!((deviation - (32f32 / 7.).sqrt()).abs() < 1e-5);
+
+        assert_eq!(mean_and_deviation(&[3.]), (3., 0.));
+    }
+
+    #[test]
+    fn overlapping_intervals_tie_with_the_best() {
+        let table = markdown_table(&[
+            entry("best", 10., 2.),
+            entry("close", 7., 1.5),
+            entry("far", 5., 1.),
+        ]);
+        let tied = table
+            .lines()
+            .skip(2)
+            .map(|line| line.trim_end_matches(" |").rsplit("| ").next().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(tied, ["yes", "yes", "no"]);
+    }
+
+    #[test]
+    fn quotes_csv_paths() {
+        assert_eq!(csv_field("training/a.json"), "training/a.json");
+        assert_eq!(csv_field("runs,2/a.json"), "\"runs,2/a.json\"");
+        assert_eq!(csv_field("the \"best\".json"), "\"the \"\"best\"\".json\"");
+
+        let mut csv = vec![];
+        write_csv(&[entry("runs,2/a.json", 1., 0.)], &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(
+            csv.lines().nth(1).unwrap(),
+            "1,\"runs,2/a.json\",4,1,0,0,2,0"
+        );
+    }
+
+    #[test]
+    fn skips_colonies_with_other_network_sizes() {
+        let dir = std::env::temp_dir().join(format!("tournament-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let save = |name: &str, inputs: usize| {
+            let network = NeuralNetwork::zero(inputs, NEURAL_NETWORK_OUTPUT_SIZE);
+            let colony = SavedColony::from_castes(vec![Caste::new("ant", network)]);
+            let path = dir.join(name);
+            fs::write(&path, serde_json::to_string(&colony).unwrap()).unwrap();
+            path
+        };
+
+        let current = save("current.json", NEURAL_NETWORK_INPUT_SIZE);
+        let outdated = save("outdated.json", NEURAL_NETWORK_INPUT_SIZE - 1);
+
+        assert_eq!(load_colony(&current).unwrap().len(), 1);
+        let error = load_colony(&outdated).unwrap_err();
+        assert!(error.contains("inputs"), "Unexpected error {}", error);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
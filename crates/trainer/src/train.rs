@@ -4,33 +4,72 @@ use chrono::Local;
 use console::Term;
 use fancy_duration::AsFancyDuration;
 use glam::vec2;
-use itertools::{izip, Itertools};
+use itertools::Itertools;
 use neural_network::NeuralNetwork;
 use rand::{thread_rng, Rng};
 use rayon::prelude::*;
-use simulation::Simulation;
+use simulation::castes::{Caste, SavedColony};
+use simulation::episode::{EpisodePolicy, Termination};
+use simulation::recorder::{Metric, Recorder};
+use simulation::scenario::Scenario;
+use simulation::{Simulation, NEURAL_NETWORK_INPUT_SIZE, NEURAL_NETWORK_OUTPUT_SIZE};
 
-use crate::STEPS_PER_SIMULATION;
+/// score per step left when all food got delivered before the episode ran out
+const COMPLETION_REWARD_PER_STEP: f32 = 1.;
 
 pub struct Trainer {
     simulations: Vec<SimulationData>,
     simulation_count: usize,
     perturbed_count: usize,
+    scenario: Scenario,
+    episode: EpisodePolicy,
+    outputs: TrainingOutputs,
+}
+
+/// files written for the best colony after every generation
+pub struct TrainingOutputs {
+    /// chrome trace json
+    pub trace: Option<String>,
+    /// time series of the metrics, csv or json lines depending on the extension
+    pub record: Option<String>,
+    pub metrics: Vec<Metric>,
+    pub record_every: usize,
 }
 
 struct SimulationData {
     base: Simulation,
     perturbed: Vec<Simulation>,
     reward: f32,
+    recorder: Option<Recorder>,
 }
 
 impl Trainer {
-    pub fn new(simulation_count: usize, perturbed_count: usize) -> Trainer {
+    /// every caste gets its own freshly initialized neural-network, all caste networks of a colony are evolved together
+    pub fn new(
+        simulation_count: usize,
+        perturbed_count: usize,
+        castes: Vec<Caste>,
+        scenario: Scenario,
+        episode: EpisodePolicy,
+        outputs: TrainingOutputs,
+    ) -> Trainer {
         let simulations = (0..simulation_count)
             .map(|_| SimulationData {
-                base: Simulation::default(),
+                base: Simulation::with_scenario(
+                    scenario.clone(),
+                    castes
+                        .iter()
+                        .map(|caste| {
+                            caste.with_neural_network(NeuralNetwork::new(
+                                NEURAL_NETWORK_INPUT_SIZE,
+                                NEURAL_NETWORK_OUTPUT_SIZE,
+                            ))
+                        })
+                        .collect(),
+                ),
                 perturbed: vec![],
                 reward: 0.,
+                recorder: None,
             })
             .collect_vec();
 
@@ -38,6 +77,9 @@ impl Trainer {
             simulations,
             simulation_count,
             perturbed_count,
+            scenario,
+            episode,
+            outputs,
         }
     }
 
@@ -58,9 +100,12 @@ impl Trainer {
                 for data in &mut self.simulations {
                     data.perturbed = (0..self.perturbed_count)
                         .map(|_| {
-                            let mut network = data.base.neural_network().clone();
-                            network.randomize_weights(0.05, 1.);
-                            Simulation::new(network)
+                            Simulation::with_scenario(
+                                data.base.scenario().clone(),
+                                Self::map_networks(data.base.castes(), |network| {
+                                    network.randomize_weights(0.05, 1.)
+                                }),
+                            )
                         })
                         .collect_vec();
                 }
@@ -69,55 +114,103 @@ impl Trainer {
                 Self::run(
                     self.simulations
                         .iter_mut()
-                        .map(|data| &mut data.perturbed)
-                        .flat_map(|permuted| permuted)
+                        .flat_map(|data| &mut data.perturbed)
                         .collect(),
+                    &self.episode,
                 );
 
                 //gradient ascent
                 for data in &mut self.simulations {
-                    let perturbed = data
+                    let rewards = data
                         .perturbed
                         .iter()
-                        .map(|simulation| (simulation.neural_network(), Self::eval(simulation)))
+                        .map(|simulation| Self::eval(simulation, &self.episode))
                         .collect_vec();
 
-                    data.base
-                        .neural_network_mut()
-                        .gradient_ascent(0.5, perturbed);
+                    for (index, caste) in data.base.castes_mut().iter_mut().enumerate() {
+                        let perturbed = data
+                            .perturbed
+                            .iter()
+                            .map(|simulation| &simulation.castes()[index].neural_network)
+                            .zip(rewards.iter().cloned())
+                            .collect_vec();
+
+                        caste.neural_network.gradient_ascent(0.5, perturbed);
+                    }
+                }
+            }
+
+            // recorded phase timings need the profiler, it is disabled otherwise
+            if self.outputs.record.is_some() && self.outputs.metrics.contains(&Metric::PhaseTimings)
+            {
+                for data in &mut self.simulations {
+                    data.base.profiler_mut().set_enabled(true);
                 }
             }
 
             // run base simulations
-            Self::run(
-                self.simulations
-                    .iter_mut()
-                    .map(|data| &mut data.base)
-                    .collect(),
-            );
+            let (episode, outputs) = (&self.episode, &self.outputs);
+            self.simulations.par_iter_mut().for_each(|data| {
+                let mut recorder = outputs
+                    .record
+                    .as_ref()
+                    .map(|_| Recorder::new(outputs.metrics.clone(), outputs.record_every));
+
+                data.base.run_episode_with(episode, |simulation| {
+                    if let Some(recorder) = &mut recorder {
+                        recorder.record(simulation);
+                    }
+                });
+
+                data.recorder = recorder;
+            });
 
             for sim in &mut self.simulations {
-                sim.reward = Self::eval(&sim.base)
+                sim.reward = Self::eval(&sim.base, &self.episode)
             }
 
             self.simulations
                 .sort_by(|a, b| b.reward.total_cmp(&a.reward));
             self.simulations
-                .dedup_by(|a, b| a.base.neural_network() == b.base.neural_network());
+                .dedup_by(|a, b| a.base.castes() == b.base.castes());
+
+            // rerunning the best colony gives the same episode without tracing all of them
+            if let Some(path) = &self.outputs.trace {
+                let best = &self.simulations[0].base;
+                let mut simulation =
+                    Simulation::with_scenario(best.scenario().clone(), best.castes().to_vec());
+                simulation.profiler_mut().set_enabled(true);
+                simulation.profiler_mut().set_trace_enabled(true);
+                simulation.run_episode(&self.episode);
+
+                let trace = simulation.profiler().chrome_trace();
+                fs::write(path, serde_json::to_string(&trace).unwrap())?;
+            }
+
+            if let (Some(path), Some(recorder)) =
+                (&self.outputs.record, &self.simulations[0].recorder)
+            {
+                recorder.save(path)?;
+            }
 
             Self::save_network(
                 gen_count,
                 self.simulations[0].reward,
-                self.simulations[0].base.neural_network(),
+                self.simulations[0].base.castes(),
             );
 
+            let foraging = self.simulations[0].base.stats().foraging_summary();
+
             term.clear_line()?;
             term.write_line(&format!(
-                "gen({}) score: {} avg({}) - {}",
+                "gen({}) score: {} avg({}) trips: {} median trip: {:.0} ticks steps: {} - {}",
                 gen_count,
                 self.simulations[0].reward,
                 self.simulations.iter().map(|data| data.reward).sum::<f32>()
                     / self.simulations.len() as f32,
+                foraging.trips,
+                foraging.median_trip_ticks.unwrap_or(0.),
+                self.simulations[0].base.stats().step_count,
                 start_time.elapsed().fancy_duration().truncate(2)
             ))?;
             term.move_cursor_up(1)?;
@@ -129,9 +222,13 @@ impl Trainer {
             // keep top 30% as is
             for i in 0..top_30.min(self.simulations.len()) {
                 new_simulations.push(SimulationData {
-                    base: Simulation::new(self.simulations[i].base.neural_network().clone()),
+                    base: Simulation::with_scenario(
+                        self.scenario.clone(),
+                        self.simulations[i].base.castes().to_vec(),
+                    ),
                     perturbed: vec![],
                     reward: 0.,
+                    recorder: None,
                 });
             }
 
@@ -140,10 +237,7 @@ impl Trainer {
 
             for i in 0..self.simulations.len() {
                 last_chance += self.simulations[i].reward;
-                network_chances.push((
-                    last_chance,
-                    self.simulations[i].base.neural_network().clone(),
-                ));
+                network_chances.push((last_chance, self.simulations[i].base.castes()));
             }
 
             let mut rng = thread_rng();
@@ -151,19 +245,20 @@ impl Trainer {
             'outer: while new_simulations.len() != self.simulation_count {
                 let random = rng.gen_range(0. ..last_chance);
 
-                for (chance, network) in &network_chances {
+                for (chance, castes) in &network_chances {
                     if &random <= chance {
-                        let mut neural_network = network.clone();
-
-                        for _ in 0..rng.gen_range(0..5) {
-                            neural_network.mutate_strucutre();
-                        }
-                        neural_network.randomize_weights(0.2, 0.5);
+                        let castes = Self::map_networks(castes, |neural_network| {
+                            for _ in 0..rng.gen_range(0..5) {
+                                neural_network.mutate_strucutre();
+                            }
+                            neural_network.randomize_weights(0.2, 0.5);
+                        });
 
                         new_simulations.push(SimulationData {
-                            base: Simulation::new(neural_network),
+                            base: Simulation::with_scenario(self.scenario.clone(), castes),
                             perturbed: vec![],
                             reward: 0.,
+                            recorder: None,
                         });
 
                         continue 'outer;
@@ -175,35 +270,51 @@ impl Trainer {
         }
     }
 
-    fn run(networks: Vec<&mut Simulation>) {
+    /// copies the castes and applies `f` to each of the copied networks
+    fn map_networks<F: FnMut(&mut NeuralNetwork)>(castes: &[Caste], mut f: F) -> Vec<Caste> {
+        castes
+            .iter()
+            .map(|caste| {
+                let mut neural_network = caste.neural_network.clone();
+                f(&mut neural_network);
+                caste.with_neural_network(neural_network)
+            })
+            .collect()
+    }
+
+    fn run(networks: Vec<&mut Simulation>, episode: &EpisodePolicy) {
         networks.into_par_iter().for_each(|simulation| {
-            for _ in 0..STEPS_PER_SIMULATION {
-                simulation.step();
-            }
+            simulation.run_episode(episode);
         });
     }
 
-    fn eval(simulation: &Simulation) -> f32 {
+    pub fn eval(simulation: &Simulation, episode: &EpisodePolicy) -> f32 {
         let mut score = 0.;
 
-        for (carries, position) in izip!(
-            &simulation.ants().caries_foods,
-            &simulation.ants().positions
-        ) {
-            if *carries {
-                score += 1. - (position.distance(vec2(0., 0.)) / 1000.);
+        for ant in simulation.ants().iter() {
+            if ant.carries_food() {
+                let (_, distance_squared) = simulation.scenario().closest_nest(ant.pos());
+                score += 1. - (distance_squared.sqrt() / 1000.);
             } else {
-                score += 1. - (position.distance(vec2(325., 325.)) / 1000.);
+                score += 1. - (ant.pos().distance(vec2(325., 325.)) / 1000.);
             }
         }
 
         score += simulation.stats().dropped_of_food as f32 * 5.
             + simulation.stats().picked_up_food as f32;
 
+        // finishing faster is better
+        if simulation.termination(episode) == Some(Termination::AllFoodDelivered) {
+            score += episode
+                .max_steps
+                .saturating_sub(simulation.stats().step_count) as f32
+                * COMPLETION_REWARD_PER_STEP;
+        }
+
         score
     }
 
-    fn save_network(gen: usize, score: f32, best_network: &NeuralNetwork) {
+    fn save_network(gen: usize, score: f32, best_castes: &[Caste]) {
         let path_string = format!(
             "./training/{}-{}-{}.json",
             gen,
@@ -214,6 +325,7 @@ impl Trainer {
         let prefix = path.parent().unwrap();
         std::fs::create_dir_all(prefix).unwrap();
 
-        fs::write(path, serde_json::to_string(best_network).unwrap()).unwrap();
+        let colony = SavedColony::from_castes(best_castes.to_vec());
+        fs::write(path, serde_json::to_string(&colony).unwrap()).unwrap();
     }
 }